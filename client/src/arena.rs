@@ -0,0 +1,116 @@
+use crate::components::*;
+use crate::map::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+// 塗れないタイルの見た目
+pub const WALL_COLOR: Color = Color::rgb(0.25, 0.25, 0.3);
+const UNPAINTABLE_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+const WATER_COLOR: Color = Color::rgb(0.05, 0.1, 0.3);
+pub const OBJECTIVE_COLOR: Color = Color::rgba(1.0, 0.9, 0.2, 0.25);
+
+pub fn setup_map(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    mut game_state: ResMut<GameState>,
+    mut ground_tiles: ResMut<GroundTiles>,
+    mut wall_tiles: ResMut<WallTiles>,
+) {
+    spawn_map_tiles(
+        &mut commands,
+        &arena.map,
+        &mut game_state,
+        &mut ground_tiles,
+        &mut wall_tiles,
+    );
+}
+
+// マップのタイルと目印のスプライトを生成する。塗れる床と壁だけを索引に登録する
+pub fn spawn_map_tiles(
+    commands: &mut Commands,
+    map: &MapDef,
+    game_state: &mut GameState,
+    ground_tiles: &mut GroundTiles,
+    wall_tiles: &mut WallTiles,
+) {
+    ground_tiles.0.clear();
+    wall_tiles.0.clear();
+    game_state.clear_turf();
+    game_state.total_tiles = map.count(TileKind::Paintable);
+
+    for (tile, kind) in map.iter_tiles() {
+        let position = tile_to_world(tile);
+        let (color, z) = match kind {
+            TileKind::Paintable => (Color::WHITE, 0.0),
+            TileKind::Wall => (WALL_COLOR, 0.2),
+            TileKind::Unpaintable => (UNPAINTABLE_COLOR, 0.0),
+            TileKind::Water => (WATER_COLOR, 0.0),
+        };
+
+        let mut entity = commands.spawn((
+            MapTile,
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(position.x, position.y, z),
+                ..default()
+            },
+        ));
+
+        match kind {
+            TileKind::Paintable => {
+                entity.insert((PaintableGround, PaintColor(Color::WHITE)));
+                ground_tiles.0.insert(tile, entity.id());
+            }
+            TileKind::Wall => {
+                entity.insert((PaintableWall, PaintColor(WALL_COLOR)));
+                wall_tiles.0.insert(tile, entity.id());
+            }
+            TileKind::Unpaintable | TileKind::Water => {}
+        }
+    }
+
+    // リスポーン地点
+    for (team, spawn) in map.spawns.iter() {
+        let Some(team) = Team::from_name(team) else {
+            continue;
+        };
+        let position = tile_to_world(*spawn);
+
+        commands.spawn((
+            MapTile,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: team.color().with_a(0.4),
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(position.x, position.y, 0.5),
+                ..default()
+            },
+        ));
+    }
+
+    for (index, objective) in map.objectives.iter().enumerate() {
+        let size = Vec2::new(objective.size.0 as f32, objective.size.1 as f32) * TILE_SIZE;
+        // position は左下のタイルなので、スプライトの中心は半タイル戻した位置から size の半分先
+        let center = tile_to_world(objective.position) - Vec2::splat(TILE_SIZE / 2.0) + size / 2.0;
+
+        commands.spawn((
+            MapTile,
+            ObjectiveMarker(index),
+            SpriteBundle {
+                sprite: Sprite {
+                    color: OBJECTIVE_COLOR,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_xyz(center.x, center.y, 0.5),
+                ..default()
+            },
+        ));
+    }
+}
@@ -0,0 +1,266 @@
+use crate::components::*;
+use crate::map::*;
+use crate::resources::*;
+use crate::systems::*;
+use crate::udp_network::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use uuid::Uuid;
+
+// この割合を下回ったら戦うのをやめ、自陣へ戻ってインクを補給する
+const BOT_LOW_INK: f32 = 0.15;
+// この割合を下回ったらイカになって自陣で補給しながら移動する
+const BOT_REFILL_INK: f32 = 0.4;
+// 塗りに行く場所を選ぶときに見比べるタイルの数
+const BOT_DESTINATION_SAMPLES: usize = 16;
+
+// --bots <数> でボットを加える。--bot-difficulty easy / normal / hard で強さを選ぶ。
+// 協力モードでは全員味方、それ以外では相手チームから交互に入れる。
+// オンラインではボットもそれぞれ別のクライアントとしてサーバーに参加する
+pub fn spawn_bots_from_args(
+    mut commands: Commands,
+    mut network_client: ResMut<NetworkClient>,
+    game_mode: Res<GameMode>,
+    arena: Res<Arena>,
+    weapon_registry: Res<WeaponRegistry>,
+    sub_weapon_registry: Res<SubWeaponRegistry>,
+    simulation_rng: Res<SimulationRng>,
+) {
+    let args = std::env::args().collect::<Vec<_>>();
+    let value_of = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+    };
+
+    let Some(mut count) = value_of("--bots").and_then(|count| count.parse::<usize>().ok()) else {
+        return;
+    };
+    // 協力モードは自分と分割画面の 2P を含めて COOP_MAX_PLAYERS 人まで
+    if *game_mode == GameMode::CoopWaves {
+        let humans = if args.iter().any(|arg| arg == "--split-screen") {
+            2
+        } else {
+            1
+        };
+        let limit = COOP_MAX_PLAYERS - humans;
+        if count > limit {
+            warn!(
+                "Coop allows {} players, adding only {} bots",
+                COOP_MAX_PLAYERS, limit
+            );
+            count = limit;
+        }
+    }
+    let difficulty = value_of("--bot-difficulty")
+        .map(|name| {
+            BotDifficulty::from_name(name).unwrap_or_else(|| {
+                warn!("Unknown bot difficulty: {}", name);
+                BotDifficulty::default()
+            })
+        })
+        .unwrap_or_default();
+
+    let mut weapons = weapon_registry
+        .weapons
+        .iter()
+        .filter(|(_, weapon)| !weapon.objective_only)
+        .map(|(id, _)| id.as_str())
+        .collect::<Vec<_>>();
+    weapons.sort_unstable();
+    // 武器もシードから選び、同じシードなら同じ顔ぶれになるようにする
+    let mut rng = fastrand::Rng::with_seed(simulation_rng.seed);
+
+    for index in 0..count {
+        let team = if *game_mode == GameMode::CoopWaves || index % 2 == 1 {
+            Team::Blue
+        } else {
+            Team::Orange
+        };
+        let weapon = if weapons.is_empty() {
+            DEFAULT_WEAPON
+        } else {
+            weapons[rng.usize(..weapons.len())]
+        };
+
+        let player_id = Uuid::new_v4();
+        let entity = spawn_player(
+            &mut commands,
+            player_id,
+            team,
+            arena.spawn_point(team),
+            weapon,
+            &weapon_registry,
+            &sub_weapon_registry,
+        );
+        commands.entity(entity).insert(Bot::new(difficulty));
+        if !network_client.offline {
+            connect_extra_udp(&mut network_client, player_id, team, *game_mode);
+        }
+        info!(
+            "🤖 Added {:?} bot to {} team with {}",
+            difficulty,
+            team.name(),
+            weapon
+        );
+    }
+}
+
+// まだ自チームの色でない塗れる床から、近い所を選ぶ
+fn pick_destination(
+    arena: &Arena,
+    game_state: &GameState,
+    team: Team,
+    from: Vec2,
+    rng: &mut SimulationRng,
+) -> Option<Vec2> {
+    let floor = arena
+        .map
+        .iter_tiles()
+        .filter(|(_, kind)| *kind == TileKind::Paintable)
+        .map(|(tile, _)| tile_to_world(tile))
+        .collect::<Vec<_>>();
+    if floor.is_empty() {
+        return None;
+    }
+
+    (0..BOT_DESTINATION_SAMPLES)
+        .map(|_| floor[rng.usize(..floor.len())])
+        .filter(|position| game_state.team_at(*position) != Some(team))
+        .min_by(|a, b| a.distance(from).total_cmp(&b.distance(from)))
+}
+
+// ボットが狙う相手になりうるもの（プレイヤー・他クライアントのプレイヤー・協力モードの敵）
+#[derive(SystemParam)]
+pub struct BotTargets<'w, 's> {
+    players: Query<'w, 's, (&'static Transform, &'static Player), Without<Splatted>>,
+    remotes: Query<'w, 's, (&'static Transform, &'static RemotePlayer), Without<Splatted>>,
+    enemies: Query<'w, 's, &'static Transform, With<Enemy>>,
+}
+
+impl<'w, 's> BotTargets<'w, 's> {
+    // team の相手で、from から sight 以内にいる一番近い位置
+    fn nearest_enemy(&self, team: Team, from: Vec2, sight: f32) -> Option<Vec2> {
+        let local = self
+            .players
+            .iter()
+            .map(|(transform, other)| (transform, other.team));
+        let remote = self
+            .remotes
+            .iter()
+            .map(|(transform, remote)| (transform, remote.team));
+        let enemies = self.enemies.iter().map(|transform| (transform, ENEMY_TEAM));
+        local
+            .chain(remote)
+            .chain(enemies)
+            .filter(|(_, other)| *other != team)
+            .map(|(transform, _)| transform.translation.truncate())
+            .filter(|target| target.distance(from) <= sight)
+            .min_by(|a, b| a.distance(from).total_cmp(&b.distance(from)))
+    }
+}
+
+// ボットの操作を決めて PlayerInput に書き込む。
+// 見える範囲に敵がいれば撃ち合い、体力かインクが減ったら自陣へ逃げ、それ以外は塗りに行く
+#[allow(clippy::type_complexity)]
+pub fn think_bots(
+    mut bot_query: Query<
+        (
+            &Transform,
+            &Player,
+            &Health,
+            &InkTank,
+            &Weapon,
+            &SubWeapon,
+            &SpecialGauge,
+            &mut Bot,
+            &mut PlayerInput,
+        ),
+        Without<Splatted>,
+    >,
+    targets: BotTargets,
+    game_state: Res<GameState>,
+    arena: Res<Arena>,
+    mut rng: ResMut<SimulationRng>,
+    time: Res<Time>,
+) {
+    for (transform, player, health, ink_tank, weapon, sub_weapon, gauge, mut bot, mut input) in
+        bot_query.iter_mut()
+    {
+        let position = transform.translation.truncate();
+        let difficulty = bot.difficulty;
+        let rethink = bot.think.tick(time.delta()).just_finished();
+
+        let target = targets.nearest_enemy(player.team, position, difficulty.sight());
+
+        if rethink {
+            bot.aim_error = (rng.f32() - 0.5) * 2.0 * difficulty.aim_error().to_radians();
+        }
+
+        let stats = &weapon.stats;
+        let charges = stats.charge_time > 0.0;
+        let was_firing = input.fire;
+        let mut next = PlayerInput::default();
+
+        // 体力が減ったら、自陣で回復しきるまで戻らない
+        bot.retreating =
+            health.0 <= difficulty.retreat_health() || (bot.retreating && health.0 < MAX_HEALTH);
+        if bot.retreating || ink_tank.fraction() < BOT_LOW_INK {
+            // 自陣へ戻り、イカになって回復する
+            next.movement = (arena.spawn_point(player.team) - position).normalize_or_zero();
+            next.squid = true;
+        } else if let Some(target) = target {
+            let offset = target - position;
+            let distance = offset.length();
+            let range = if stats.kind == WeaponKind::Roller {
+                stats.roll_width
+            } else {
+                stats.range
+            };
+
+            // 射程の少し内側を保ち、ちょうどよい距離なら横に動いてかわす
+            next.movement = if distance > range * 0.8 {
+                offset.normalize_or_zero()
+            } else if distance < range * 0.4 && stats.kind != WeaponKind::Roller {
+                -offset.normalize_or_zero()
+            } else {
+                offset.perp().normalize_or_zero()
+            };
+            next.aim = Some(position + Vec2::from_angle(bot.aim_error).rotate(offset));
+            // 溜める武器は考え直すたびに離して撃つ
+            next.fire = !(charges && rethink);
+            next.special = gauge.is_full();
+            next.sub_weapon = rethink
+                && distance <= sub_weapon.stats.throw_range
+                && !ink_tank.is_low(sub_weapon.stats.ink_cost + stats.ink_cost * 10.0)
+                && rng.f32() < 0.25;
+        } else {
+            // 動けていなければ行き先を選び直す
+            let stuck = bot.last_position.distance(position) < 1.0;
+            let arrived = bot
+                .destination
+                .is_none_or(|destination| destination.distance(position) < TILE_SIZE);
+            if rethink && (stuck || arrived) {
+                bot.destination =
+                    pick_destination(&arena, &game_state, player.team, position, &mut rng);
+            }
+
+            if let Some(destination) = bot.destination {
+                let offset = destination - position;
+                next.movement = offset.normalize_or_zero();
+                if ink_tank.fraction() < BOT_REFILL_INK {
+                    next.squid = true;
+                } else {
+                    next.aim = Some(position + offset);
+                    next.fire = !(charges && rethink);
+                }
+            }
+        }
+
+        if rethink {
+            bot.last_position = position;
+        }
+        next.fire_released = was_firing && !next.fire;
+        *input = next;
+    }
+}
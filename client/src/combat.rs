@@ -0,0 +1,361 @@
+use crate::components::*;
+use crate::events::*;
+use crate::paint::*;
+use crate::resources::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use uuid::Uuid;
+
+// プレイヤーの当たり判定の半径（スプライト 30px + 弾 8px）
+pub const PLAYER_HIT_RADIUS: f32 = 19.0;
+// やられた場所に広がるインクの半径
+pub const SPLAT_PAINT_RADIUS: f32 = 72.0;
+
+// 攻撃の当たり判定の対象になるプレイヤー
+#[derive(SystemParam)]
+#[allow(clippy::type_complexity)]
+pub struct Combatants<'w, 's> {
+    players: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Transform,
+            &'static Player,
+            &'static mut Health,
+        ),
+        (
+            Without<Splatted>,
+            Without<SpawnProtection>,
+            Without<InkArmor>,
+        ),
+    >,
+    remotes: Query<
+        'w,
+        's,
+        (Entity, &'static Transform, &'static RemotePlayer),
+        (Without<Splatted>, Without<InkArmor>),
+    >,
+    enemies: Query<'w, 's, (Entity, &'static Transform, &'static mut Enemy)>,
+    network_client: Res<'w, NetworkClient>,
+    hit_events: EventWriter<'w, PlayerHitEvent>,
+    splat_events: EventWriter<'w, PlayerSplattedEvent>,
+}
+
+impl<'w, 's> Combatants<'w, 's> {
+    // hit(位置) が true になる敵すべてに攻撃を当て、当たった相手を返す。spared の相手には当てない
+    pub fn strike(
+        &mut self,
+        attacker: Uuid,
+        team: Team,
+        damage: i32,
+        spared: &[Entity],
+        hit: impl Fn(Vec2) -> bool,
+    ) -> Vec<Entity> {
+        let mut struck = Vec::new();
+
+        // 自分への被弾はこのクライアントで判定してダメージを受ける。
+        // 同じティックで既にやられていれば、やられた扱いを重ねない
+        for (entity, transform, player, mut health) in self.players.iter_mut() {
+            let position = transform.translation.truncate();
+            if player.team == team || health.0 <= 0 || spared.contains(&entity) || !hit(position) {
+                continue;
+            }
+
+            struck.push(entity);
+            health.0 -= damage;
+            self.hit_events.send(PlayerHitEvent {
+                target: player.id,
+                attacker,
+                damage,
+            });
+
+            if health.0 <= 0 {
+                self.splat_events.send(PlayerSplattedEvent {
+                    victim: player.id,
+                    attacker,
+                    attacker_team: team,
+                    position,
+                });
+            }
+        }
+
+        // 他プレイヤーへの命中は表示のみ。ダメージは相手のクライアントが判定する
+        for (entity, transform, remote) in self.remotes.iter() {
+            if remote.team == team
+                || spared.contains(&entity)
+                || !hit(transform.translation.truncate())
+            {
+                continue;
+            }
+
+            struck.push(entity);
+            self.hit_events.send(PlayerHitEvent {
+                target: remote.id,
+                attacker,
+                damage,
+            });
+        }
+
+        // 協力モードの敵。他プレイヤーの弾も中継されてホストで再現されるので、
+        // 体力を減らすのはホストだけで、他のクライアントは coop_state に合わせる
+        if team != ENEMY_TEAM {
+            let host = self.network_client.is_host();
+            for (entity, transform, mut enemy) in self.enemies.iter_mut() {
+                if enemy.health <= 0
+                    || spared.contains(&entity)
+                    || !hit(transform.translation.truncate())
+                {
+                    continue;
+                }
+
+                struck.push(entity);
+                if host {
+                    enemy.health -= damage;
+                }
+                self.hit_events.send(PlayerHitEvent {
+                    target: ENEMY_ID,
+                    attacker,
+                    damage,
+                });
+            }
+        }
+
+        struck
+    }
+
+    // 直線上で最初に当たる敵までの距離
+    pub fn first_on_line(
+        &self,
+        team: Team,
+        origin: Vec2,
+        direction: Vec2,
+        length: f32,
+    ) -> Option<f32> {
+        let local = self
+            .players
+            .iter()
+            .map(|(_, transform, player, _)| (transform, player.team));
+        let remote = self
+            .remotes
+            .iter()
+            .map(|(_, transform, remote)| (transform, remote.team));
+
+        let enemies = self
+            .enemies
+            .iter()
+            .filter(|(_, _, enemy)| enemy.health > 0)
+            .map(|(_, transform, _)| (transform, ENEMY_TEAM));
+
+        local
+            .chain(remote)
+            .chain(enemies)
+            .filter(|(_, other)| *other != team)
+            .filter_map(|(transform, _)| {
+                let offset = transform.translation.truncate() - origin;
+                let along = offset.dot(direction);
+                let across = (offset - direction * along).length();
+                (along >= 0.0 && along <= length && across <= PLAYER_HIT_RADIUS).then_some(along)
+            })
+            .min_by(|a, b| a.total_cmp(b))
+    }
+}
+
+// 弾と敵プレイヤーの当たり判定
+pub fn projectile_hits(
+    mut commands: Commands,
+    projectile_query: Query<(Entity, &Transform, &Projectile)>,
+    mut combatants: Combatants,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+) {
+    for (entity, transform, projectile) in projectile_query.iter() {
+        let position = transform.translation.truncate();
+
+        // 遠くで当たるほどダメージが下がる
+        let struck = combatants.strike(
+            projectile.owner,
+            projectile.team,
+            projectile.damage_at(position),
+            &[],
+            |target| target.distance(position) <= PLAYER_HIT_RADIUS,
+        );
+        if struck.is_empty() {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        // ブラスターの弾は直撃でも爆発する。直撃した相手に爆風は重ねない
+        if projectile.explosion_radius > 0.0 {
+            explosion_events.send(ExplosionEvent {
+                owner: projectile.owner,
+                team: projectile.team,
+                position,
+                radius: projectile.explosion_radius,
+                damage: projectile.damage / 2,
+                spared: struck,
+            });
+        }
+    }
+}
+
+// 爆発の範囲を塗り、範囲内の敵にダメージを与える
+pub fn resolve_explosions(
+    mut explosion_events: EventReader<ExplosionEvent>,
+    mut painter: GroundPainter,
+    mut combatants: Combatants,
+) {
+    for explosion in explosion_events.read() {
+        let inked =
+            painter.paint_circle(explosion.position, explosion.radius, explosion.team.color());
+        painter.credit(explosion.owner, inked);
+        combatants.strike(
+            explosion.owner,
+            explosion.team,
+            explosion.damage,
+            &explosion.spared,
+            |target| target.distance(explosion.position) <= explosion.radius,
+        );
+    }
+}
+
+// やられたプレイヤーはその場で攻撃側の色のインクを撒き散らす。
+// 他プレイヤーも、リスポーンするまで隠して当たらないようにする
+#[allow(clippy::type_complexity)]
+pub fn splat_players(
+    mut commands: Commands,
+    mut splat_events: EventReader<PlayerSplattedEvent>,
+    mut player_query: Query<(Entity, &Player, &mut Visibility), Without<Splatted>>,
+    mut remote_query: Query<
+        (Entity, &RemotePlayer, &mut Visibility),
+        (Without<Splatted>, Without<Player>),
+    >,
+    mut painter: GroundPainter,
+) {
+    for event in splat_events.read() {
+        painter.paint_circle(
+            event.position,
+            SPLAT_PAINT_RADIUS,
+            event.attacker_team.color(),
+        );
+
+        for (entity, player, mut visibility) in player_query.iter_mut() {
+            if player.id == event.victim {
+                commands.entity(entity).insert(Splatted::default());
+                *visibility = Visibility::Hidden;
+            }
+        }
+        for (entity, remote, mut visibility) in remote_query.iter_mut() {
+            if remote.id == event.victim {
+                commands.entity(entity).insert(Splatted::default());
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
+
+// やられた他プレイヤーはリスポーンの時間が過ぎたらまた表示する。位置は相手のクライアントから届く
+pub fn respawn_remote_players(
+    mut commands: Commands,
+    mut remote_query: Query<(Entity, &mut Splatted, &mut Visibility), With<RemotePlayer>>,
+    time: Res<Time>,
+) {
+    for (entity, mut splatted, mut visibility) in remote_query.iter_mut() {
+        if splatted.respawn.tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<Splatted>();
+            *visibility = Visibility::Visible;
+        }
+    }
+}
+
+// 自チームのリスポーン地点に戻し、体力とインクを回復させる
+fn respawn(
+    commands: &mut Commands,
+    entity: Entity,
+    spawn: Vec2,
+    transform: &mut Transform,
+    health: &mut Health,
+    ink_tank: &mut InkTank,
+    visibility: &mut Visibility,
+) {
+    transform.translation.x = spawn.x;
+    transform.translation.y = spawn.y;
+    health.0 = MAX_HEALTH;
+    ink_tank.ink = ink_tank.capacity;
+    *visibility = Visibility::Visible;
+
+    commands
+        .entity(entity)
+        .remove::<Splatted>()
+        .insert(SpawnProtection::default());
+}
+
+pub fn respawn_players(
+    mut commands: Commands,
+    mut player_query: Query<(
+        Entity,
+        &Player,
+        &mut Splatted,
+        &mut Transform,
+        &mut Health,
+        &mut InkTank,
+        &mut Visibility,
+    )>,
+    arena: Res<Arena>,
+    time: Res<Time>,
+) {
+    for (entity, player, mut splatted, mut transform, mut health, mut ink_tank, mut visibility) in
+        player_query.iter_mut()
+    {
+        if splatted.respawn.tick(time.delta()).just_finished() {
+            respawn(
+                &mut commands,
+                entity,
+                arena.spawn_point(player.team),
+                &mut transform,
+                &mut health,
+                &mut ink_tank,
+                &mut visibility,
+            );
+        }
+    }
+}
+
+pub fn tick_spawn_protection(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &mut SpawnProtection)>,
+    time: Res<Time>,
+) {
+    for (entity, mut protection) in player_query.iter_mut() {
+        if protection.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<SpawnProtection>();
+        }
+    }
+}
+
+// 新しい試合の開始時に全員をリスポーン地点へ戻す
+pub fn revive_players(
+    mut commands: Commands,
+    mut player_query: Query<(
+        Entity,
+        &Player,
+        &mut Transform,
+        &mut Health,
+        &mut InkTank,
+        &mut Visibility,
+    )>,
+    arena: Res<Arena>,
+) {
+    for (entity, player, mut transform, mut health, mut ink_tank, mut visibility) in
+        player_query.iter_mut()
+    {
+        respawn(
+            &mut commands,
+            entity,
+            arena.spawn_point(player.team),
+            &mut transform,
+            &mut health,
+            &mut ink_tank,
+            &mut visibility,
+        );
+    }
+}
@@ -2,29 +2,477 @@ use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::resources::{BotDifficulty, EnemyKind, SubWeaponDef, Team, WeaponDef};
+
 #[derive(Component, Serialize, Deserialize)]
 pub struct Player {
     pub id: Uuid,
+    pub team: Team,
 }
 
 #[derive(Component)]
 pub struct Velocity(pub Vec2);
 
+pub const MAX_HEALTH: i32 = 100;
+// 自チームのインクに潜っている間と、自陣のリスポーン地点の周りで回復する体力
+pub const HEALTH_REGEN_PER_SECOND: f32 = 40.0;
+
 #[derive(Component)]
 pub struct Health(pub i32);
 
+pub const RESPAWN_SECONDS: f32 = 5.0;
+pub const SPAWN_PROTECTION_SECONDS: f32 = 2.0;
+
+// やられて行動できない状態。タイマーが終わるとリスポーンする
+#[derive(Component)]
+pub struct Splatted {
+    pub respawn: Timer,
+}
+
+impl Default for Splatted {
+    fn default() -> Self {
+        Self {
+            respawn: Timer::from_seconds(RESPAWN_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+// リスポーン直後の無敵時間
+#[derive(Component)]
+pub struct SpawnProtection(pub Timer);
+
+impl Default for SpawnProtection {
+    fn default() -> Self {
+        Self(Timer::from_seconds(
+            SPAWN_PROTECTION_SECONDS,
+            TimerMode::Once,
+        ))
+    }
+}
+
+// 敵インクから受ける継続ダメージの端数
+#[derive(Component, Default)]
+pub struct InkDamage {
+    pub accumulated: f32,
+}
+
+// 回復する体力の端数
+#[derive(Component, Default)]
+pub struct HealthRegen {
+    pub accumulated: f32,
+}
+
 #[derive(Component)]
 pub struct PaintableGround;
 
+#[derive(Component)]
+pub struct PaintableWall;
+
 #[derive(Component)]
 pub struct PaintColor(pub Color);
 
+// マップから生成したタイル。マップを作り直すときにまとめて消す
+#[derive(Component)]
+pub struct MapTile;
+
+// マップの objectives の何番目の目印か
+#[derive(Component)]
+pub struct ObjectiveMarker(pub usize);
+
+// 弾の放物線の最高点（見た目の高さ）
+pub const PROJECTILE_ARC_HEIGHT: f32 = 24.0;
+// 飛んでいる弾はこの距離ごとに足元へインクを垂らす
+pub const PROJECTILE_DRIP_INTERVAL: f32 = 48.0;
+// 射程のこの割合を超えるとダメージが減り始め、射程の端で MIN 倍になる
+pub const DAMAGE_FALLOFF_START: f32 = 0.5;
+pub const DAMAGE_FALLOFF_MIN: f32 = 0.5;
+
 #[derive(Component)]
 pub struct Projectile {
     pub owner: Uuid,
+    pub team: Team,
     pub color: Color,
-    pub lifetime: f32,
+    pub origin: Vec2,
+    pub range: f32,
+    pub damage: i32,
+    pub paint_radius: f32,
+    // 0 より大きければ射程の終わりで爆発する（ブラスター）
+    pub explosion_radius: f32,
+    // 次にインクを垂らす飛距離
+    pub next_drip: f32,
+}
+
+impl Projectile {
+    // 射程に対する飛距離の割合（0〜1）
+    pub fn progress(&self, position: Vec2) -> f32 {
+        (self.origin.distance(position) / self.range).min(1.0)
+    }
+
+    pub fn height(&self, position: Vec2) -> f32 {
+        let t = self.progress(position);
+        PROJECTILE_ARC_HEIGHT * 4.0 * t * (1.0 - t)
+    }
+
+    pub fn damage_at(&self, position: Vec2) -> i32 {
+        let t = self.progress(position);
+        let falloff = ((t - DAMAGE_FALLOFF_START) / (1.0 - DAMAGE_FALLOFF_START)).max(0.0);
+        let scale = 1.0 - (1.0 - DAMAGE_FALLOFF_MIN) * falloff;
+        (self.damage as f32 * scale).round() as i32
+    }
+}
+
+// プレイヤーを追いかけるカメラ。分割画面ではプレイヤーごとに一つずつある
+#[derive(Component)]
+pub struct PlayerCamera {
+    pub player: Entity,
+}
+
+// 分割画面で HUD だけを画面全体に描くカメラ
+#[derive(Component)]
+pub struct HudCamera;
+
+// 装備中のメイン武器。性能は WeaponRegistry から読み込んだもの
+#[derive(Component)]
+pub struct Weapon {
+    pub id: String,
+    pub stats: WeaponDef,
+}
+
+// 装備中のサブウェポン
+#[derive(Component)]
+pub struct SubWeapon {
+    pub id: String,
+    pub stats: SubWeaponDef,
 }
 
+// 投げられたボム。放物線を描いて着地し、導火線が尽きると爆発する
 #[derive(Component)]
-pub struct PlayerCamera;
\ No newline at end of file
+pub struct Bomb {
+    pub owner: Uuid,
+    pub team: Team,
+    pub start: Vec2,
+    pub target: Vec2,
+    pub flight: Timer,
+    pub fuse: Timer,
+    pub explosion_radius: f32,
+    pub damage: i32,
+}
+
+// 次の弾を撃てるまでの残り秒数。間隔は武器の fire_rate で決まる
+#[derive(Component, Default)]
+pub struct FireCooldown(pub f32);
+
+impl FireCooldown {
+    // 撃てるようになった後は減らさないので、持ち越すのは最後のティックの超過分だけ
+    pub fn tick(&mut self, delta: f32) {
+        if self.0 > 0.0 {
+            self.0 -= delta;
+        }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.0 <= 0.0
+    }
+
+    // 超過した分を次の間隔から差し引き、ティックの刻みに関係なく fire_rate で撃てるようにする
+    pub fn restart(&mut self, fire_rate: f32) {
+        if fire_rate > 0.0 {
+            self.0 = self.0.min(0.0) + 1.0 / fire_rate;
+        }
+    }
+}
+
+// チャージャーの溜め時間
+#[derive(Component, Default)]
+pub struct ChargeState {
+    pub charge: f32,
+}
+
+// ローラーで最後に塗った位置
+#[derive(Component, Default)]
+pub struct RollState {
+    pub last_position: Option<Vec2>,
+}
+
+// チャージャーの弾道を一瞬だけ表示する
+#[derive(Component)]
+pub struct BeamEffect(pub Timer);
+
+pub const INK_REFILL_PER_SECOND: f32 = 8.0;
+pub const INK_REFILL_ON_OWN_INK_PER_SECOND: f32 = 20.0;
+pub const INK_REFILL_SUBMERGED_PER_SECOND: f32 = 45.0;
+
+#[derive(Component)]
+pub struct InkTank {
+    pub ink: f32,
+    pub capacity: f32,
+}
+
+impl Default for InkTank {
+    fn default() -> Self {
+        Self {
+            ink: 100.0,
+            capacity: 100.0,
+        }
+    }
+}
+
+impl InkTank {
+    // 1発分のインクが残っていなければ撃てない
+    pub fn is_low(&self, cost: f32) -> bool {
+        self.ink < cost
+    }
+
+    pub fn consume(&mut self, cost: f32) {
+        self.ink = (self.ink - cost).max(0.0);
+    }
+
+    pub fn refill(&mut self, amount: f32) {
+        self.ink = (self.ink + amount).min(self.capacity);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.ink / self.capacity
+    }
+}
+
+// 新しく塗ったタイル数でスペシャルゲージが溜まる
+pub const SPECIAL_POINTS_REQUIRED: f32 = 150.0;
+pub const INK_ARMOR_SECONDS: f32 = 6.0;
+
+#[derive(Component, Default)]
+pub struct SpecialGauge {
+    pub points: f32,
+}
+
+impl SpecialGauge {
+    pub fn add(&mut self, tiles: usize) {
+        self.points = (self.points + tiles as f32).min(SPECIAL_POINTS_REQUIRED);
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.points >= SPECIAL_POINTS_REQUIRED
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.points / SPECIAL_POINTS_REQUIRED
+    }
+}
+
+// スペシャル「インクアーマー」。効果中は攻撃を受けない
+#[derive(Component)]
+pub struct InkArmor(pub Timer);
+
+impl Default for InkArmor {
+    fn default() -> Self {
+        Self(Timer::from_seconds(INK_ARMOR_SECONDS, TimerMode::Once))
+    }
+}
+
+// アーマーの見た目（プレイヤーの子エンティティ）
+#[derive(Component)]
+pub struct ArmorShell;
+
+// イカ状態。自チームのインクに潜っている間は submerged になる
+#[derive(Component, Default)]
+pub struct SquidForm {
+    pub active: bool,
+    pub submerged: bool,
+}
+
+// サーバーから同期される他プレイヤー
+#[derive(Component)]
+pub struct RemotePlayer {
+    pub id: Uuid,
+    pub team: Team,
+}
+
+#[derive(Component)]
+pub struct MatchTimerText;
+
+#[derive(Component)]
+pub struct ResultsScreen;
+
+#[derive(Component)]
+pub struct CoverageFill(pub Team);
+
+#[derive(Component)]
+pub struct CoverageLabel(pub Team);
+
+#[derive(Component)]
+pub struct InkGaugeFill;
+
+#[derive(Component)]
+pub struct LowInkLabel;
+
+#[derive(Component)]
+pub struct HealthText;
+
+#[derive(Component)]
+pub struct SpecialGaugeFill;
+
+#[derive(Component)]
+pub struct EditorText;
+
+// ガチヤグラのスプライト
+#[derive(Component)]
+pub struct TowerMarker;
+
+// ガチホコのスプライト
+#[derive(Component)]
+pub struct RainmakerMarker;
+
+// ガチホコを持っている。落としたら元の武器に戻す
+#[derive(Component)]
+pub struct RainmakerCarrier {
+    pub previous_weapon: String,
+}
+
+// 協力モードの敵。体力はホストが判定する
+#[derive(Component)]
+pub struct Enemy {
+    pub id: u32,
+    pub kind: EnemyKind,
+    pub health: i32,
+}
+
+// 敵の体当たり。触れている間 cooldown ごとにダメージを与える
+#[derive(Component)]
+pub struct EnemyAttack {
+    pub cooldown: Timer,
+    pub damage: i32,
+    pub reach: f32,
+}
+
+// ボスが落とした金イクラ
+#[derive(Component)]
+pub struct Egg {
+    pub id: u32,
+}
+
+// 金イクラを1個持っている
+#[derive(Component)]
+pub struct CarryingEgg;
+
+// 金イクラを納品するカゴ
+#[derive(Component)]
+pub struct BasketMarker;
+
+// プレイヤーへの操作。人の操作もボットの操作もここを通して動かす
+#[derive(Component, Default, Clone)]
+pub struct PlayerInput {
+    pub movement: Vec2,
+    // 狙っているワールド座標
+    pub aim: Option<Vec2>,
+    pub fire: bool,
+    // 撃つボタンを離した瞬間（溜め撃ち用）
+    pub fire_released: bool,
+    pub squid: bool,
+    pub sub_weapon: bool,
+    pub special: bool,
+    pub switch_weapon: bool,
+}
+
+impl PlayerInput {
+    // 押しっぱなしの操作は新しいものにし、押した・離した瞬間の操作は次のティックで使うまで残す
+    pub fn latch(&mut self, next: PlayerInput) {
+        *self = PlayerInput {
+            fire_released: self.fire_released || next.fire_released,
+            sub_weapon: self.sub_weapon || next.sub_weapon,
+            special: self.special || next.special,
+            switch_weapon: self.switch_weapon || next.switch_weapon,
+            ..next
+        };
+    }
+
+    pub fn clear_edges(&mut self) {
+        self.fire_released = false;
+        self.sub_weapon = false;
+        self.special = false;
+        self.switch_weapon = false;
+    }
+}
+
+// 固定ティックで動かす物の位置。描画は直前の2ティックの間を補間する
+#[derive(Component)]
+pub struct SimulatedPosition {
+    pub previous: Vec2,
+    pub current: Vec2,
+    // 最後に描画用に Transform へ書いた位置。これと違えばティックの外で動かされた
+    pub rendered: Vec2,
+}
+
+impl SimulatedPosition {
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            previous: position,
+            current: position,
+            rendered: position,
+        }
+    }
+}
+
+// ゲームパッドで操作するプレイヤー（分割画面の 2P）。キーボードとマウスは 1P が使う
+#[derive(Component)]
+pub struct GamepadControl;
+
+// 2P の体力・インク・スペシャルを出す文字
+#[derive(Component)]
+pub struct SecondPlayerText;
+
+// AI が操作するプレイヤー
+#[derive(Component)]
+pub struct Bot {
+    pub difficulty: BotDifficulty,
+    // 次に考え直すまでの時間。難しいほど短い
+    pub think: Timer,
+    // 塗りに向かっている場所
+    pub destination: Option<Vec2>,
+    pub last_position: Vec2,
+    pub aim_error: f32,
+    // 体力が減って自陣へ逃げている。回復しきるまで戻らない
+    pub retreating: bool,
+}
+
+impl Bot {
+    pub fn new(difficulty: BotDifficulty) -> Self {
+        Self {
+            difficulty,
+            think: Timer::from_seconds(difficulty.reaction_seconds(), TimerMode::Repeating),
+            destination: None,
+            last_position: Vec2::ZERO,
+            aim_error: 0.0,
+            retreating: false,
+        }
+    }
+}
+
+// ルールごとのカウント表示（ナワバリバトルでは空）
+#[derive(Component)]
+pub struct ModeScoreText;
+
+#[derive(Component)]
+pub struct SpecialReadyLabel;
+
+#[derive(Component)]
+pub struct KillFeedText;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fire_cooldown_keeps_fire_rate_across_ticks() {
+        // 64 Hz で 10 発/秒。超過分を持ち越さないと 7 ティックごと（約 9.1 発/秒）になる
+        let mut cooldown = FireCooldown::default();
+        let mut shots = 0;
+        for _ in 0..640 {
+            cooldown.tick(1.0 / 64.0);
+            if cooldown.ready() {
+                cooldown.restart(10.0);
+                shots += 1;
+            }
+        }
+        assert_eq!(shots, 100);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::map::MapDef;
+use crate::resources::*;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::Path;
+
+// assets 以下の RON ファイルを読み込む。読めない場合は既定値を使う
+pub fn load_ron_or_default<T: DeserializeOwned + Default>(path: &str) -> T {
+    match load_ron(Path::new(path)) {
+        Ok(value) => value,
+        Err(e) => {
+            warn!("Failed to load {}: {}, using defaults", path, e);
+            T::default()
+        }
+    }
+}
+
+pub fn load_terrain_modifiers(mut terrain_modifiers: ResMut<TerrainModifiers>) {
+    *terrain_modifiers = load_ron_or_default(TERRAIN_CONFIG_PATH);
+}
+
+// マップファイルを読み込む。壊れている場合は何もない床だけのマップにする
+pub fn load_map(mut arena: ResMut<Arena>) {
+    let map = load_ron::<MapDef>(Path::new(DEFAULT_MAP_PATH)).and_then(|map| {
+        map.validate()?;
+        Ok(map)
+    });
+
+    match map {
+        Ok(map) => {
+            info!(
+                "🗺️ Loaded map {} ({}x{})",
+                map.name,
+                map.width(),
+                map.height()
+            );
+            arena.set_map(map);
+        }
+        Err(e) => {
+            warn!("Failed to load {}: {}, using defaults", DEFAULT_MAP_PATH, e);
+            arena.set_map(MapDef::default());
+        }
+    }
+}
+
+// assets/weapons 以下の *.ron をファイル名を ID として読み込む
+pub fn load_weapon_registry(mut registry: ResMut<WeaponRegistry>) {
+    for (id, weapon) in load_ron_dir::<WeaponDef>(WEAPONS_DIR) {
+        info!(
+            "🔫 Loaded weapon {} ({}): {:.1} shots/s, {} dmg, range {}",
+            id, weapon.name, weapon.fire_rate, weapon.damage, weapon.range
+        );
+        registry.weapons.insert(id, weapon);
+    }
+}
+
+pub fn load_sub_weapon_registry(mut registry: ResMut<SubWeaponRegistry>) {
+    for (id, sub_weapon) in load_ron_dir::<SubWeaponDef>(SUB_WEAPONS_DIR) {
+        info!(
+            "💣 Loaded sub weapon {} ({}): {} ink",
+            id, sub_weapon.name, sub_weapon.ink_cost
+        );
+        registry.sub_weapons.insert(id, sub_weapon);
+    }
+}
+
+// ディレクトリ内の *.ron を (ファイル名, 中身) の組で読み込む
+fn load_ron_dir<T: DeserializeOwned>(dir: &str) -> Vec<(String, T)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to read {}: {}", dir, e);
+            return Vec::new();
+        }
+    };
+
+    let mut loaded = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("ron") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+
+        match load_ron::<T>(&path) {
+            Ok(value) => loaded.push((id.to_string(), value)),
+            Err(e) => warn!("Failed to load {}: {}", path.display(), e),
+        }
+    }
+    loaded
+}
+
+fn load_ron<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ron::from_str(&contents).map_err(|e| e.to_string())
+}
@@ -0,0 +1,450 @@
+use crate::components::*;
+use crate::events::*;
+use crate::map::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+const BASKET_COLOR: Color = Color::rgba(0.6, 0.4, 0.2, 0.6);
+const EGG_COLOR: Color = Color::rgb(1.0, 0.8, 0.1);
+
+fn spawn_enemy(commands: &mut Commands, id: u32, kind: EnemyKind, position: Vec2) {
+    commands.spawn((
+        Enemy {
+            id,
+            kind,
+            health: kind.max_health(),
+        },
+        EnemyAttack {
+            cooldown: Timer::from_seconds(1.0, TimerMode::Once),
+            damage: kind.damage(),
+            reach: kind.size() / 2.0 + PLAYER_COLLISION_HALF_SIZE,
+        },
+        SpriteBundle {
+            sprite: Sprite {
+                color: ENEMY_TEAM.color(),
+                custom_size: Some(Vec2::splat(kind.size())),
+                ..default()
+            },
+            transform: Transform::from_xyz(position.x, position.y, 0.95),
+            ..default()
+        },
+        SimulatedPosition::new(position),
+    ));
+}
+
+fn spawn_egg(commands: &mut Commands, id: u32, position: Vec2) {
+    commands.spawn((
+        Egg { id },
+        SpriteBundle {
+            sprite: Sprite {
+                color: EGG_COLOR,
+                custom_size: Some(Vec2::splat(12.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(position.x, position.y, 0.9),
+            ..default()
+        },
+    ));
+}
+
+// 試合開始時に敵と金イクラを片付け、カゴを置く。
+// カゴは team のない Goal の目印（なければマップの中央）
+#[allow(clippy::type_complexity)]
+pub fn reset_coop_waves(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    mut coop_waves: ResMut<CoopWaves>,
+    leftover_query: Query<Entity, Or<(With<Enemy>, With<Egg>, With<BasketMarker>)>>,
+    carrier_query: Query<Entity, With<CarryingEgg>>,
+) {
+    for entity in leftover_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for entity in carrier_query.iter() {
+        commands.entity(entity).remove::<CarryingEgg>();
+    }
+
+    let map = &arena.map;
+    let basket = map
+        .objectives
+        .iter()
+        .find(|objective| objective.kind == ObjectiveKind::Goal && objective.team.is_none())
+        .map(|objective| {
+            let size = Vec2::new(objective.size.0 as f32 - 1.0, objective.size.1 as f32 - 1.0);
+            tile_to_world(objective.position) + size * TILE_SIZE / 2.0
+        })
+        .unwrap_or_else(|| {
+            warn!("Map {} has no basket, using the center", map.name);
+            let size = Vec2::new(map.width() as f32 - 1.0, map.height() as f32 - 1.0);
+            tile_to_world(map.origin) + size * TILE_SIZE / 2.0
+        });
+
+    // マップの外周の床のうち、プレイヤーのリスポーン地点から離れた所から湧く
+    let (left, bottom) = map.origin;
+    let (right, top) = (left + map.width() - 1, bottom + map.height() - 1);
+    let spawn_edges = map
+        .iter_tiles()
+        .filter(|(tile, kind)| {
+            (tile.0 == left || tile.0 == right || tile.1 == bottom || tile.1 == top)
+                && matches!(kind, TileKind::Paintable | TileKind::Unpaintable)
+        })
+        .map(|(tile, _)| tile_to_world(tile))
+        .filter(|position| {
+            arena.spawn_point(Team::Blue).distance(*position) > SPAWN_AREA_RADIUS * 2.0
+        })
+        .collect::<Vec<_>>();
+    if spawn_edges.is_empty() {
+        warn!("Map {} has no floor on its edges for enemies", map.name);
+    }
+
+    commands.spawn((
+        BasketMarker,
+        SpriteBundle {
+            sprite: Sprite {
+                color: BASKET_COLOR,
+                custom_size: Some(Vec2::splat(BASKET_RADIUS * 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(basket.x, basket.y, 0.5),
+            ..default()
+        },
+    ));
+
+    *coop_waves = CoopWaves::new(basket, spawn_edges);
+}
+
+// 波の時間を進めて敵を湧かせ、波の終わりにノルマを確かめる
+pub fn update_coop_waves(
+    mut commands: Commands,
+    mut coop_waves: ResMut<CoopWaves>,
+    enemy_query: Query<&Enemy>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+    mut rng: ResMut<SimulationRng>,
+    time: Res<Time>,
+) {
+    if coop_waves.finished() {
+        return;
+    }
+
+    let minions = coop_waves
+        .minion_timer
+        .tick(time.delta())
+        .times_finished_this_tick();
+    let bosses = coop_waves
+        .boss_timer
+        .tick(time.delta())
+        .times_finished_this_tick();
+    let spawns = std::iter::repeat_n(EnemyKind::Minion, minions as usize)
+        .chain(std::iter::repeat_n(EnemyKind::Boss, bosses as usize));
+    let room = MAX_ENEMIES.saturating_sub(enemy_query.iter().count());
+    if !coop_waves.spawn_edges.is_empty() {
+        for kind in spawns.take(room) {
+            let edge = coop_waves.spawn_edges[rng.usize(..coop_waves.spawn_edges.len())];
+            let id = coop_waves.next_id();
+            spawn_enemy(&mut commands, id, kind, edge);
+        }
+    }
+
+    if !coop_waves.wave_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let wave = coop_waves.wave;
+    coop_waves.end_wave();
+    if coop_waves.failed {
+        info!("🥚 Wave {} failed", wave);
+    } else if coop_waves.cleared {
+        info!("🥚 All {} waves cleared!", COOP_WAVES);
+    } else {
+        info!("🥚 Wave {} cleared, wave {} begins", wave, coop_waves.wave);
+    }
+
+    if coop_waves.finished() {
+        next_phase.set(MatchPhase::Finished);
+    }
+}
+
+// 敵は一番近いプレイヤーへ向かう
+#[allow(clippy::type_complexity)]
+pub fn move_enemies(
+    mut enemy_query: Query<(&Enemy, &mut Transform), (Without<Player>, Without<RemotePlayer>)>,
+    player_query: Query<&Transform, (With<Player>, Without<Splatted>)>,
+    remote_query: Query<&Transform, (With<RemotePlayer>, Without<Splatted>)>,
+    arena: Res<Arena>,
+    time: Res<Time>,
+) {
+    let targets = player_query
+        .iter()
+        .chain(remote_query.iter())
+        .map(|transform| transform.translation.truncate())
+        .collect::<Vec<_>>();
+
+    for (enemy, mut transform) in enemy_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let Some(target) = targets
+            .iter()
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+        else {
+            continue;
+        };
+
+        let delta =
+            (*target - position).normalize_or_zero() * enemy.kind.speed() * time.delta_seconds();
+        let next =
+            quantize_position(
+                arena.slide(position, delta, enemy.kind.size() / 2.0 - 2.0, |_| false),
+            );
+        transform.translation.x = next.x;
+        transform.translation.y = next.y;
+    }
+}
+
+// 触れている敵が自分にダメージを与える。被弾の判定はいつも通り自分のクライアントで行う
+#[allow(clippy::type_complexity)]
+pub fn enemy_attacks(
+    mut attack_query: Query<(&Transform, &mut EnemyAttack), With<Enemy>>,
+    mut player_query: Query<
+        (&Transform, &Player, &mut Health),
+        (
+            Without<Splatted>,
+            Without<SpawnProtection>,
+            Without<InkArmor>,
+        ),
+    >,
+    mut hit_events: EventWriter<PlayerHitEvent>,
+    mut splat_events: EventWriter<PlayerSplattedEvent>,
+    time: Res<Time>,
+) {
+    for (transform, mut attack) in attack_query.iter_mut() {
+        attack.cooldown.tick(time.delta());
+        if !attack.cooldown.finished() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        for (player_transform, player, mut health) in player_query.iter_mut() {
+            let target = player_transform.translation.truncate();
+            if health.0 <= 0 || target.distance(position) > attack.reach {
+                continue;
+            }
+
+            attack.cooldown.reset();
+            health.0 -= attack.damage;
+            hit_events.send(PlayerHitEvent {
+                target: player.id,
+                attacker: ENEMY_ID,
+                damage: attack.damage,
+            });
+            if health.0 <= 0 {
+                splat_events.send(PlayerSplattedEvent {
+                    victim: player.id,
+                    attacker: ENEMY_ID,
+                    attacker_team: ENEMY_TEAM,
+                    position: target,
+                });
+            }
+        }
+    }
+}
+
+// 体力が尽きた敵を消す。ボスは金イクラを落とす
+pub fn defeat_enemies(
+    mut commands: Commands,
+    mut coop_waves: ResMut<CoopWaves>,
+    enemy_query: Query<(Entity, &Enemy, &Transform)>,
+) {
+    for (entity, enemy, transform) in enemy_query.iter() {
+        if enemy.health > 0 {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        if enemy.kind != EnemyKind::Boss {
+            continue;
+        }
+
+        info!("🥚 Boss defeated!");
+        let position = transform.translation.truncate();
+        for index in 0..BOSS_EGGS {
+            let offset = Vec2::from_angle(index as f32 / BOSS_EGGS as f32 * std::f32::consts::TAU)
+                * TILE_SIZE
+                / 2.0;
+            let id = coop_waves.next_id();
+            spawn_egg(&mut commands, id, position + offset);
+        }
+    }
+}
+
+// 手ぶらで金イクラに触れたら拾う
+#[allow(clippy::type_complexity)]
+pub fn collect_eggs(
+    mut commands: Commands,
+    player_query: Query<(Entity, &Transform, &Player), (Without<Splatted>, Without<CarryingEgg>)>,
+    egg_query: Query<(&Transform, &Egg)>,
+    mut collected_events: EventWriter<EggCollectedEvent>,
+) {
+    for (entity, transform, player) in player_query.iter() {
+        let position = transform.translation.truncate();
+        let Some((_, egg)) = egg_query.iter().find(|(egg_transform, _)| {
+            egg_transform.translation.truncate().distance(position) <= EGG_PICKUP_RADIUS
+        }) else {
+            continue;
+        };
+
+        commands.entity(entity).insert(CarryingEgg);
+        collected_events.send(EggCollectedEvent {
+            player: player.id,
+            egg: egg.id,
+        });
+    }
+}
+
+// 金イクラを持ってカゴに入ったら納品する
+pub fn deposit_eggs(
+    mut commands: Commands,
+    coop_waves: Res<CoopWaves>,
+    player_query: Query<(Entity, &Transform, &Player), With<CarryingEgg>>,
+    mut deposited_events: EventWriter<EggDepositedEvent>,
+) {
+    for (entity, transform, player) in player_query.iter() {
+        if transform.translation.truncate().distance(coop_waves.basket) > BASKET_RADIUS {
+            continue;
+        }
+
+        commands.entity(entity).remove::<CarryingEgg>();
+        deposited_events.send(EggDepositedEvent { player: player.id });
+    }
+}
+
+// やられたら持っていた金イクラはなくなる
+pub fn lose_eggs_on_splat(
+    mut commands: Commands,
+    mut splat_events: EventReader<PlayerSplattedEvent>,
+    player_query: Query<(Entity, &Player), With<CarryingEgg>>,
+) {
+    for event in splat_events.read() {
+        for (entity, player) in player_query.iter() {
+            if player.id == event.victim {
+                commands.entity(entity).remove::<CarryingEgg>();
+            }
+        }
+    }
+}
+
+// 拾われた金イクラを消し、納品数を数える（自分・他プレイヤーとも）。
+// 納品数はホストが数え、他のクライアントは coop_state で受け取る
+pub fn resolve_egg_events(
+    mut commands: Commands,
+    mut collected_events: EventReader<EggCollectedEvent>,
+    mut deposited_events: EventReader<EggDepositedEvent>,
+    mut coop_waves: ResMut<CoopWaves>,
+    egg_query: Query<(Entity, &Egg)>,
+    network_client: Res<NetworkClient>,
+) {
+    for event in collected_events.read() {
+        for (entity, egg) in egg_query.iter() {
+            if egg.id == event.egg {
+                commands.entity(entity).despawn();
+            }
+        }
+        coop_waves.eggs.retain(|(id, _)| *id != event.egg);
+    }
+
+    for _ in deposited_events.read() {
+        if network_client.is_host() {
+            coop_waves.delivered += 1;
+        }
+    }
+}
+
+// ホスト以外は、ホストから届いた敵と金イクラに表示を合わせる
+pub fn apply_coop_snapshot(
+    mut commands: Commands,
+    coop_waves: Res<CoopWaves>,
+    mut enemy_query: Query<(Entity, &mut Enemy, &mut Transform)>,
+    egg_query: Query<(Entity, &Egg)>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+) {
+    if !coop_waves.is_changed() {
+        return;
+    }
+
+    let mut known = std::collections::HashSet::new();
+    for (entity, mut enemy, mut transform) in enemy_query.iter_mut() {
+        let Some(state) = coop_waves.enemies.iter().find(|state| state.id == enemy.id) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        known.insert(enemy.id);
+        enemy.health = state.health;
+        transform.translation.x = state.position.x;
+        transform.translation.y = state.position.y;
+    }
+    for state in coop_waves.enemies.iter() {
+        if !known.contains(&state.id) {
+            spawn_enemy(&mut commands, state.id, state.kind, state.position);
+        }
+    }
+
+    let mut known = std::collections::HashSet::new();
+    for (entity, egg) in egg_query.iter() {
+        if coop_waves.eggs.iter().any(|(id, _)| *id == egg.id) {
+            known.insert(egg.id);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (id, position) in coop_waves.eggs.iter() {
+        if !known.contains(id) {
+            spawn_egg(&mut commands, *id, *position);
+        }
+    }
+
+    if coop_waves.finished() {
+        next_phase.set(MatchPhase::Finished);
+    }
+}
+
+pub fn tally_coop_waves(coop_waves: Res<CoopWaves>, mut result: ResMut<MatchResult>) {
+    let headline = if coop_waves.cleared {
+        "Clear!".to_string()
+    } else {
+        format!("Failed at wave {}", coop_waves.wave)
+    };
+
+    *result = MatchResult {
+        winner: coop_waves.cleared.then_some(Team::Blue),
+        scores: vec![(
+            Team::Blue,
+            format!(
+                "wave {} - {}/{} eggs",
+                coop_waves.wave,
+                coop_waves.delivered,
+                coop_waves.quota()
+            ),
+        )],
+        headline: Some(headline),
+    };
+}
+
+pub fn update_coop_text(
+    coop_waves: Res<CoopWaves>,
+    player_query: Query<Option<&CarryingEgg>, (With<Player>, Without<Bot>)>,
+    mut text_query: Query<&mut Text, With<ModeScoreText>>,
+) {
+    let mut label = format!(
+        "WAVE {}/{}   EGGS {}/{}   {}s",
+        coop_waves.wave,
+        COOP_WAVES,
+        coop_waves.delivered,
+        coop_waves.quota(),
+        coop_waves.wave_timer.remaining_secs().ceil() as u32
+    );
+    if player_query.iter().any(|carrying| carrying.is_some()) {
+        label.push_str("   CARRYING EGG");
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = label.clone();
+    }
+}
@@ -0,0 +1,342 @@
+use crate::arena::*;
+use crate::components::*;
+use crate::map::*;
+use crate::resources::*;
+use crate::systems::*;
+use bevy::prelude::*;
+use std::fs;
+
+const EDITOR_PAN_SPEED: f32 = 600.0;
+
+// --editor を付けて起動したらエディタから始める
+pub fn start_editor_from_args(mut next_state: ResMut<NextState<EditorState>>) {
+    if std::env::args().any(|arg| arg == "--editor") {
+        next_state.set(EditorState::On);
+    }
+}
+
+pub fn toggle_editor(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<EditorState>>,
+    mut next_state: ResMut<NextState<EditorState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F1) {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        EditorState::Off => EditorState::On,
+        EditorState::On => EditorState::Off,
+    });
+}
+
+pub fn spawn_editor_text(mut commands: Commands) {
+    info!("🛠️ Map editor enabled");
+    commands.spawn((
+        EditorText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            top: Val::Px(16.0),
+            ..default()
+        }),
+    ));
+}
+
+pub fn despawn_editor_text(mut commands: Commands, text_query: Query<Entity, With<EditorText>>) {
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// 数字キーでブラシ、Tab でチームを選ぶ
+pub fn editor_select_brush(keyboard_input: Res<Input<KeyCode>>, mut editor: ResMut<MapEditor>) {
+    let brushes = [
+        (KeyCode::Key1, EditorBrush::Tile(TileKind::Paintable)),
+        (KeyCode::Key2, EditorBrush::Tile(TileKind::Wall)),
+        (KeyCode::Key3, EditorBrush::Tile(TileKind::Unpaintable)),
+        (KeyCode::Key4, EditorBrush::Tile(TileKind::Water)),
+        (KeyCode::Key5, EditorBrush::Spawn),
+        (
+            KeyCode::Key6,
+            EditorBrush::Objective(ObjectiveKind::SplatZone),
+        ),
+        (KeyCode::Key7, EditorBrush::Objective(ObjectiveKind::Tower)),
+        (
+            KeyCode::Key8,
+            EditorBrush::Objective(ObjectiveKind::Rainmaker),
+        ),
+        (KeyCode::Key9, EditorBrush::Objective(ObjectiveKind::Goal)),
+        (KeyCode::Key0, EditorBrush::TowerPath),
+    ];
+
+    for (key, brush) in brushes {
+        if keyboard_input.just_pressed(key) {
+            editor.brush = brush;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        editor.team = editor.team.opponent();
+    }
+}
+
+pub fn editor_pan_camera(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut camera_query: Query<&mut Transform, With<PlayerCamera>>,
+    time: Res<Time>,
+) {
+    // Ctrl+S などのショートカット中は動かさない
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    let mut direction = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
+        direction.y += 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down) {
+        direction.y -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left) {
+        direction.x -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
+        direction.x += 1.0;
+    }
+
+    let offset = direction.normalize_or_zero() * EDITOR_PAN_SPEED * time.delta_seconds();
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x += offset.x;
+        transform.translation.y += offset.y;
+    }
+}
+
+// 左クリックでブラシを使い、右クリックでその位置の目印とヤグラの通り道の点を消す
+pub fn editor_paint(
+    mouse_button_input: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    mut arena: ResMut<Arena>,
+    mut editor: ResMut<MapEditor>,
+) {
+    let window = windows.single();
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+
+    // 分割画面ではカーソルのある画面のカメラで位置を求める
+    let Some(world_position) = camera_query
+        .iter()
+        .find(|(camera, _)| {
+            camera
+                .logical_viewport_rect()
+                .is_some_and(|rect| rect.contains(cursor))
+        })
+        .and_then(|(camera, camera_transform)| cursor_to_world(camera, camera_transform, cursor))
+    else {
+        return;
+    };
+    let tile = world_to_tile(world_position);
+
+    if mouse_button_input.just_pressed(MouseButton::Right) {
+        if arena.map.objectives.iter().any(|o| o.contains(tile))
+            || arena.map.tower_path.contains(&tile)
+        {
+            editor.snapshot(&arena.map);
+            arena.map.objectives.retain(|o| !o.contains(tile));
+            arena.map.tower_path.retain(|point| *point != tile);
+            editor.dirty = true;
+        }
+        return;
+    }
+
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        editor.stroke_recorded = false;
+        editor.drag_start = Some(tile);
+    }
+
+    match editor.brush {
+        EditorBrush::Tile(kind) => {
+            // 同じ種類のタイルやマップの外をなぞっても履歴は増やさない
+            if mouse_button_input.pressed(MouseButton::Left)
+                && arena.map.contains(tile)
+                && arena.map.tile(tile) != kind
+            {
+                editor.snapshot_stroke(&arena.map);
+                arena.map.set_tile(tile, kind);
+                editor.dirty = true;
+            }
+        }
+        EditorBrush::Spawn => {
+            let team = editor.team.name();
+            if mouse_button_input.just_pressed(MouseButton::Left)
+                && arena.map.contains(tile)
+                && arena.map.spawn(team) != Some(tile)
+            {
+                editor.snapshot_stroke(&arena.map);
+                arena
+                    .map
+                    .spawns
+                    .insert(editor.team.name().to_string(), tile);
+                editor.dirty = true;
+            }
+        }
+        EditorBrush::Objective(kind) => {
+            // ドラッグした範囲を目印にする
+            if !mouse_button_input.just_released(MouseButton::Left) {
+                return;
+            }
+            let Some(start) = editor.drag_start.take() else {
+                return;
+            };
+
+            let team = (kind == ObjectiveKind::Goal).then(|| editor.team.name().to_string());
+            editor.snapshot_stroke(&arena.map);
+            arena.map.objectives.push(ObjectiveDef {
+                kind,
+                position: (start.0.min(tile.0), start.1.min(tile.1)),
+                size: ((start.0 - tile.0).abs() + 1, (start.1 - tile.1).abs() + 1),
+                team,
+            });
+            editor.dirty = true;
+        }
+        EditorBrush::TowerPath => {
+            // 直前の点と同じ場所には足さない
+            if mouse_button_input.just_pressed(MouseButton::Left)
+                && arena.map.contains(tile)
+                && arena.map.tower_path.last() != Some(&tile)
+            {
+                editor.snapshot_stroke(&arena.map);
+                arena.map.tower_path.push(tile);
+                editor.dirty = true;
+            }
+        }
+    }
+}
+
+// Ctrl+Z で元に戻す、Ctrl+Y でやり直す、Ctrl+S で保存、Ctrl+O でファイルから読み直す
+pub fn editor_shortcuts(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut arena: ResMut<Arena>,
+    mut editor: ResMut<MapEditor>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Z) {
+        if let Some(previous) = editor.undo.pop() {
+            let current = std::mem::replace(&mut arena.map, previous);
+            editor.redo.push(current);
+            editor.dirty = true;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Y) {
+        if let Some(next) = editor.redo.pop() {
+            let current = std::mem::replace(&mut arena.map, next);
+            editor.undo.push(current);
+            editor.dirty = true;
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::S) {
+        if let Err(e) = save_map(&arena.map) {
+            warn!("Failed to save {}: {}", DEFAULT_MAP_PATH, e);
+        } else {
+            info!("💾 Saved map to {}", DEFAULT_MAP_PATH);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::O) {
+        match load_map_file() {
+            Ok(map) => {
+                editor.snapshot(&arena.map);
+                arena.map = map;
+                editor.dirty = true;
+                info!("📂 Reloaded map from {}", DEFAULT_MAP_PATH);
+            }
+            Err(e) => warn!("Failed to load {}: {}", DEFAULT_MAP_PATH, e),
+        }
+    }
+}
+
+fn save_map(map: &MapDef) -> Result<(), String> {
+    map.validate()?;
+    let contents = ron::ser::to_string_pretty(map, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    fs::write(DEFAULT_MAP_PATH, contents).map_err(|e| e.to_string())
+}
+
+fn load_map_file() -> Result<MapDef, String> {
+    let contents = fs::read_to_string(DEFAULT_MAP_PATH).map_err(|e| e.to_string())?;
+    let map: MapDef = ron::from_str(&contents).map_err(|e| e.to_string())?;
+    map.validate()?;
+    Ok(map)
+}
+
+// 編集されたマップのタイルを作り直す
+pub fn rebuild_edited_map(
+    mut commands: Commands,
+    mut arena: ResMut<Arena>,
+    mut editor: ResMut<MapEditor>,
+    mut game_state: ResMut<GameState>,
+    mut ground_tiles: ResMut<GroundTiles>,
+    mut wall_tiles: ResMut<WallTiles>,
+    tile_query: Query<Entity, With<MapTile>>,
+) {
+    if !editor.dirty {
+        return;
+    }
+    editor.dirty = false;
+
+    for entity in tile_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    arena.refresh_spawn_points();
+    spawn_map_tiles(
+        &mut commands,
+        &arena.map,
+        &mut game_state,
+        &mut ground_tiles,
+        &mut wall_tiles,
+    );
+}
+
+pub fn update_editor_text(
+    editor: Res<MapEditor>,
+    arena: Res<Arena>,
+    mut text_query: Query<&mut Text, With<EditorText>>,
+) {
+    let brush = match editor.brush {
+        EditorBrush::Tile(kind) => format!("{:?}", kind),
+        EditorBrush::Spawn => format!("{} spawn", editor.team.name()),
+        EditorBrush::Objective(ObjectiveKind::Goal) => format!("{} goal", editor.team.name()),
+        EditorBrush::Objective(kind) => format!("{:?}", kind),
+        EditorBrush::TowerPath => format!("tower path ({} points)", arena.map.tower_path.len()),
+    };
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "EDITOR - brush: {} (team: {})\n\
+             1 Floor  2 Wall  3 Unpaintable  4 Water  5 Spawn\n\
+             6 Splat Zone  7 Tower  8 Rainmaker  9 Goal  0 Tower Path  Tab Team\n\
+             LMB paint/drag  RMB remove objective/path point  WASD pan\n\
+             Ctrl+Z undo ({})  Ctrl+Y redo ({})  Ctrl+S save  Ctrl+O load  F1 exit",
+            brush,
+            editor.team.name(),
+            editor.undo.len(),
+            editor.redo.len()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::resources::{Team, WeaponKind};
+use bevy::prelude::*;
+use uuid::Uuid;
+
+// 弾がプレイヤーに当たった
+#[derive(Event)]
+pub struct PlayerHitEvent {
+    pub target: Uuid,
+    pub attacker: Uuid,
+    pub damage: i32,
+}
+
+// プレイヤーがやられた
+#[derive(Event)]
+pub struct PlayerSplattedEvent {
+    pub victim: Uuid,
+    pub attacker: Uuid,
+    pub attacker_team: Team,
+    pub position: Vec2,
+}
+
+// メイン武器が撃たれた。自分の射撃もサーバー経由の他プレイヤーの射撃も同じ形で扱う
+#[derive(Event, Clone)]
+pub struct ShotFiredEvent {
+    pub shooter: Uuid,
+    pub team: Team,
+    pub weapon: String,
+    pub kind: WeaponKind,
+    pub origin: Vec2,
+    pub direction: Vec2,
+    // チャージャーなど溜めて撃つ武器の溜め具合（0.0〜1.0）
+    pub charge: f32,
+    // 撃つ前のインク残量（サーバーでの検証用）
+    pub ink: f32,
+}
+
+// 爆発。範囲内を塗り、敵にダメージを与える
+#[derive(Event)]
+pub struct ExplosionEvent {
+    pub owner: Uuid,
+    pub team: Team,
+    pub position: Vec2,
+    pub radius: f32,
+    pub damage: i32,
+    // 直撃で既にダメージを受けた相手には爆風を当てない
+    pub spared: Vec<Entity>,
+}
+
+// サブウェポンが投げられた
+#[derive(Event, Clone)]
+pub struct SubWeaponThrownEvent {
+    pub thrower: Uuid,
+    pub team: Team,
+    pub sub_weapon: String,
+    pub origin: Vec2,
+    pub target: Vec2,
+    pub ink: f32,
+}
+
+// 新しくタイルを塗った。スペシャルゲージの増加に使う
+#[derive(Event)]
+pub struct TurfInkedEvent {
+    pub player: Uuid,
+    pub tiles: usize,
+}
+
+// スペシャルが発動された
+#[derive(Event, Clone)]
+pub struct SpecialActivatedEvent {
+    pub player: Uuid,
+    pub team: Team,
+}
+
+// ガチホコを拾った
+#[derive(Event, Clone)]
+pub struct RainmakerPickedUpEvent {
+    pub player: Uuid,
+    pub team: Team,
+}
+
+// 金イクラを拾った
+#[derive(Event, Clone)]
+pub struct EggCollectedEvent {
+    pub player: Uuid,
+    pub egg: u32,
+}
+
+// 金イクラをカゴに納品した
+#[derive(Event, Clone)]
+pub struct EggDepositedEvent {
+    pub player: Uuid,
+}
@@ -0,0 +1,458 @@
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+pub fn setup_hud(mut commands: Commands) {
+    commands.spawn((
+        MatchTimerText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 36.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Percent(46.0),
+            ..default()
+        }),
+    ));
+
+    commands.spawn((
+        ModeScoreText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 22.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(56.0),
+            left: Val::Percent(38.0),
+            ..default()
+        }),
+    ));
+
+    // 画面下部のカバー率バー（左が Blue、右が Orange）
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(16.0),
+                left: Val::Percent(25.0),
+                width: Val::Percent(50.0),
+                height: Val::Px(24.0),
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            background_color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for team in [Team::Blue, Team::Orange] {
+                parent
+                    .spawn((
+                        CoverageFill(team),
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                align_items: AlignItems::Center,
+                                justify_content: JustifyContent::Center,
+                                overflow: Overflow::visible(),
+                                ..default()
+                            },
+                            background_color: team.color().into(),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|fill| {
+                        fill.spawn((
+                            CoverageLabel(team),
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 18.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ),
+                        ));
+                    });
+            }
+        });
+}
+
+pub fn setup_ink_gauge(mut commands: Commands) {
+    // 画面右下のインクタンク
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(24.0),
+                bottom: Val::Px(16.0),
+                width: Val::Px(24.0),
+                height: Val::Px(160.0),
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            background_color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                InkGaugeFill,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::BLUE.into(),
+                    ..default()
+                },
+            ));
+        });
+
+    commands.spawn((
+        LowInkLabel,
+        TextBundle::from_section(
+            "LOW INK",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::RED,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(56.0),
+            bottom: Val::Px(16.0),
+            ..default()
+        }),
+        Visibility::Hidden,
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_ink_gauge(
+    player_query: Query<(&Player, &Weapon, &InkTank), (Without<Bot>, Without<GamepadControl>)>,
+    mut fill_query: Query<(&mut Style, &mut BackgroundColor), With<InkGaugeFill>>,
+    mut label_query: Query<&mut Visibility, With<LowInkLabel>>,
+) {
+    let Some((player, weapon, ink_tank)) = player_query.iter().next() else {
+        return;
+    };
+    let low_ink = ink_tank.is_low(weapon.stats.ink_cost);
+
+    for (mut style, mut background) in fill_query.iter_mut() {
+        style.height = Val::Percent(ink_tank.fraction() * 100.0);
+        background.0 = if low_ink {
+            Color::RED
+        } else {
+            player.team.color()
+        };
+    }
+
+    for mut visibility in label_query.iter_mut() {
+        *visibility = if low_ink {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+// 画面左下のスペシャルゲージ。満タンになると READY を表示する
+pub fn setup_special_gauge(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(16.0),
+                bottom: Val::Px(52.0),
+                width: Val::Px(160.0),
+                height: Val::Px(14.0),
+                ..default()
+            },
+            background_color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                SpecialGaugeFill,
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::YELLOW.into(),
+                    ..default()
+                },
+            ));
+        });
+
+    commands.spawn((
+        SpecialReadyLabel,
+        TextBundle::from_section(
+            "SPECIAL READY (E)",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            bottom: Val::Px(72.0),
+            ..default()
+        }),
+        Visibility::Hidden,
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_special_gauge(
+    player_query: Query<
+        (&SpecialGauge, Option<&InkArmor>),
+        (With<Player>, Without<Bot>, Without<GamepadControl>),
+    >,
+    mut fill_query: Query<&mut Style, With<SpecialGaugeFill>>,
+    mut label_query: Query<&mut Visibility, With<SpecialReadyLabel>>,
+) {
+    let Some((gauge, armor)) = player_query.iter().next() else {
+        return;
+    };
+
+    // アーマー中は残り時間をゲージで表示する
+    let fraction = match armor {
+        Some(armor) => 1.0 - armor.0.percent(),
+        None => gauge.fraction(),
+    };
+    for mut style in fill_query.iter_mut() {
+        style.width = Val::Percent(fraction * 100.0);
+    }
+
+    for mut visibility in label_query.iter_mut() {
+        *visibility = if gauge.is_full() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn setup_combat_hud(mut commands: Commands) {
+    commands.spawn((
+        HealthText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            bottom: Val::Px(16.0),
+            ..default()
+        }),
+    ));
+
+    commands.spawn((
+        KillFeedText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(16.0),
+            top: Val::Px(16.0),
+            ..default()
+        }),
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_health_text(
+    player_query: Query<
+        (&Health, Option<&Splatted>),
+        (With<Player>, Without<Bot>, Without<GamepadControl>),
+    >,
+    mut text_query: Query<&mut Text, With<HealthText>>,
+) {
+    let Some((health, splatted)) = player_query.iter().next() else {
+        return;
+    };
+    let label = if let Some(splatted) = splatted {
+        format!(
+            "SPLATTED - respawn in {}",
+            splatted.respawn.remaining_secs().ceil() as u32
+        )
+    } else {
+        format!("HP {}", health.0.max(0))
+    };
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = label.clone();
+    }
+}
+
+// 命中・撃破イベントをキルログに積み、古いものから消す
+pub fn update_kill_feed(
+    mut hit_events: EventReader<PlayerHitEvent>,
+    mut splat_events: EventReader<PlayerSplattedEvent>,
+    network_client: Res<NetworkClient>,
+    mut kill_feed: ResMut<KillFeed>,
+    mut text_query: Query<&mut Text, With<KillFeedText>>,
+    time: Res<Time>,
+) {
+    let own_id = network_client.player_id;
+
+    for event in hit_events.read() {
+        if event.attacker == own_id {
+            kill_feed.push(format!("Hit! -{}", event.damage));
+        } else if event.target == own_id {
+            kill_feed.push(format!("Took {} damage", event.damage));
+        }
+    }
+
+    for event in splat_events.read() {
+        let message = if event.victim == own_id {
+            "You were splatted!".to_string()
+        } else if event.attacker == own_id {
+            "You splatted an enemy!".to_string()
+        } else {
+            format!("{} team splatted a player", event.attacker_team.name())
+        };
+        kill_feed.push(message);
+    }
+
+    for (_, timer) in kill_feed.entries.iter_mut() {
+        timer.tick(time.delta());
+    }
+    kill_feed.entries.retain(|(_, timer)| !timer.finished());
+
+    let lines = kill_feed
+        .entries
+        .iter()
+        .map(|(message, _)| message.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.clone();
+    }
+}
+
+pub fn update_coverage_bar(
+    game_state: Res<GameState>,
+    mut fill_query: Query<(&CoverageFill, &mut Style)>,
+    mut label_query: Query<(&CoverageLabel, &mut Text)>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    for (fill, mut style) in fill_query.iter_mut() {
+        style.width = Val::Percent(game_state.turf_percent(fill.0));
+    }
+
+    for (label, mut text) in label_query.iter_mut() {
+        text.sections[0].value = format!("{:.1}%", game_state.turf_percent(label.0));
+    }
+}
+
+pub fn update_match_timer_text(
+    phase: Res<State<MatchPhase>>,
+    clock: Res<MatchClock>,
+    network_client: Res<NetworkClient>,
+    mut text_query: Query<&mut Text, With<MatchTimerText>>,
+) {
+    let label = match phase.get() {
+        MatchPhase::Waiting if network_client.offline => "Practice - Press Enter".to_string(),
+        MatchPhase::Waiting => "Press Enter".to_string(),
+        MatchPhase::Countdown => {
+            format!("{}", clock.countdown.remaining_secs().ceil() as u32)
+        }
+        MatchPhase::InProgress => {
+            let remaining = clock.match_timer.remaining_secs().ceil() as u32;
+            format!("{}:{:02}", remaining / 60, remaining % 60)
+        }
+        MatchPhase::Finished => "0:00".to_string(),
+    };
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = label.clone();
+    }
+}
+
+pub fn spawn_results_screen(mut commands: Commands, result: Res<MatchResult>) {
+    let headline = match (&result.headline, result.winner) {
+        (Some(headline), _) => headline.clone(),
+        (None, Some(team)) => format!("{} team wins!", team.name().to_uppercase()),
+        (None, None) => "Draw!".to_string(),
+    };
+
+    commands
+        .spawn((
+            ResultsScreen,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                headline,
+                TextStyle {
+                    font_size: 64.0,
+                    color: result.winner.map_or(Color::WHITE, Team::color),
+                    ..default()
+                },
+            ));
+            for (team, score) in result.scores.iter() {
+                parent.spawn(TextBundle::from_section(
+                    format!("{}: {}", team.name(), score),
+                    TextStyle {
+                        font_size: 40.0,
+                        color: team.color(),
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+pub fn despawn_results_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<ResultsScreen>>,
+) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,16 +1,50 @@
+use bevy::input::InputSystem;
 use bevy::prelude::*;
 use bevy::window::WindowPlugin;
 
+mod arena;
+mod bots;
+mod combat;
 mod components;
+mod config;
+mod coop_waves;
+mod editor;
+mod events;
+mod hud;
+mod map;
+mod match_flow;
 mod network;
+mod paint;
+mod rainmaker;
 mod resources;
+mod simulation;
+mod special;
+mod splat_zones;
+mod split_screen;
 mod systems;
+mod tower_control;
 mod udp_network;
+mod weapons;
 
-use components::*;
+use arena::*;
+use bots::*;
+use combat::*;
+use config::*;
+use coop_waves::*;
+use editor::*;
+use events::*;
+use hud::*;
+use match_flow::*;
+use rainmaker::*;
 use resources::*;
+use simulation::*;
+use special::*;
+use splat_zones::*;
+use split_screen::*;
 use systems::*;
+use tower_control::*;
 use udp_network::*;
+use weapons::*;
 
 fn main() {
     App::new()
@@ -23,33 +57,296 @@ fn main() {
             ..default()
         }))
         .init_resource::<GameState>()
+        .init_resource::<GroundTiles>()
+        .init_resource::<WallTiles>()
+        .init_resource::<Arena>()
+        .init_resource::<TerrainModifiers>()
+        .init_resource::<WeaponRegistry>()
+        .init_resource::<SubWeaponRegistry>()
         .init_resource::<NetworkClient>()
-        .add_systems(Startup, (setup, setup_udp_network))
+        .init_resource::<MatchClock>()
+        .init_resource::<MatchResult>()
+        .init_resource::<KillFeed>()
+        .init_resource::<MapEditor>()
+        .init_resource::<GameMode>()
+        .init_resource::<SplatZones>()
+        .init_resource::<TowerControl>()
+        .init_resource::<Rainmaker>()
+        .init_resource::<CoopWaves>()
+        .init_resource::<SimulationTick>()
+        .init_resource::<SimulationRng>()
+        .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+        .add_state::<MatchPhase>()
+        .add_state::<EditorState>()
+        .add_event::<PlayerHitEvent>()
+        .add_event::<PlayerSplattedEvent>()
+        .add_event::<ShotFiredEvent>()
+        .add_event::<ExplosionEvent>()
+        .add_event::<SubWeaponThrownEvent>()
+        .add_event::<TurfInkedEvent>()
+        .add_event::<SpecialActivatedEvent>()
+        .add_event::<RainmakerPickedUpEvent>()
+        .add_event::<EggCollectedEvent>()
+        .add_event::<EggDepositedEvent>()
+        .add_systems(
+            Startup,
+            (
+                (
+                    load_terrain_modifiers,
+                    load_weapon_registry,
+                    load_sub_weapon_registry,
+                    load_map,
+                )
+                    .before(setup)
+                    .before(setup_map)
+                    .before(spawn_bots_from_args),
+                setup,
+                setup_map,
+                setup_hud,
+                setup_ink_gauge,
+                setup_combat_hud,
+                setup_special_gauge,
+                setup_udp_network
+                    .after(select_offline_from_args)
+                    .after(select_host_from_args)
+                    .after(select_game_mode_from_args),
+                select_offline_from_args,
+                select_host_from_args,
+                start_editor_from_args,
+                select_game_mode_from_args,
+                select_seed_from_args,
+                spawn_bots_from_args
+                    .after(select_game_mode_from_args)
+                    .after(select_seed_from_args)
+                    .after(select_offline_from_args),
+            ),
+        )
+        // setup のカメラと 1P ができてから 2P を加える
+        .add_systems(PostStartup, spawn_split_screen_from_args)
+        .add_systems(OnEnter(MatchPhase::Waiting), reset_match_clock)
+        .add_systems(
+            OnExit(MatchPhase::Waiting),
+            (
+                reset_simulation_tick,
+                reset_turf,
+                revive_players,
+                reset_special_gauges,
+                reset_splat_zones.run_if(resource_equals(GameMode::SplatZones)),
+                reset_tower_control.run_if(resource_equals(GameMode::TowerControl)),
+                reset_rainmaker.run_if(resource_equals(GameMode::Rainmaker)),
+                reset_coop_waves.run_if(resource_equals(GameMode::CoopWaves)),
+            ),
+        )
+        .add_systems(
+            OnEnter(MatchPhase::Finished),
+            (
+                tally_results.run_if(resource_equals(GameMode::TurfWar)),
+                tally_splat_zones.run_if(resource_equals(GameMode::SplatZones)),
+                tally_tower_control.run_if(resource_equals(GameMode::TowerControl)),
+                tally_rainmaker.run_if(resource_equals(GameMode::Rainmaker)),
+                tally_coop_waves.run_if(resource_equals(GameMode::CoopWaves)),
+                spawn_results_screen,
+            )
+                .chain(),
+        )
+        .add_systems(OnExit(MatchPhase::Finished), despawn_results_screen)
+        .add_systems(OnEnter(EditorState::On), spawn_editor_text)
+        .add_systems(OnExit(EditorState::On), despawn_editor_text)
+        .add_systems(
+            Update,
+            (
+                (
+                    lobby_ready,
+                    // エディタの Ctrl+O と重ならないようにする
+                    toggle_offline.run_if(in_state(EditorState::Off)),
+                )
+                    .run_if(in_state(MatchPhase::Waiting)),
+                update_match_timer_text,
+                update_coverage_bar,
+                update_ink_gauge,
+                update_health_text,
+                update_kill_feed,
+                update_special_gauge,
+                (layout_split_screen, update_second_player_text),
+                toggle_editor,
+                (update_zone_markers, update_splat_zones_text)
+                    .run_if(resource_equals(GameMode::SplatZones)),
+                (update_tower_sprite, update_tower_text)
+                    .run_if(resource_equals(GameMode::TowerControl)),
+                (update_rainmaker_sprite, update_rainmaker_text)
+                    .run_if(resource_equals(GameMode::Rainmaker)),
+                update_coop_text.run_if(resource_equals(GameMode::CoopWaves)),
+            ),
+        )
         .add_systems(
             Update,
             (
-                player_movement,
-                send_player_position_udp,
-                send_shoot_action_udp,
-                local_shooting,
-                paint_system,
-                camera_follow,
-                handle_udp_messages,
+                editor_select_brush,
+                editor_pan_camera,
+                editor_paint,
+                editor_shortcuts,
+                rebuild_edited_map,
+                update_editor_text,
+            )
+                .chain()
+                .run_if(in_state(EditorState::On)),
+        )
+        .add_systems(
+            PreUpdate,
+            (read_keyboard_input, read_gamepad_input)
+                .after(InputSystem)
+                .run_if(in_state(EditorState::Off)),
+        )
+        // 試合の進行は固定ティックで動かす。どの端末でも同じ順に処理されるよう、すべて直列に並べる
+        .add_systems(
+            FixedUpdate,
+            (
+                (
+                    advance_simulation_tick,
+                    restore_simulated_positions,
+                    handle_udp_messages,
+                )
+                    .chain(),
+                (
+                    think_bots,
+                    squid_form_toggle,
+                    player_movement,
+                    switch_weapon,
+                )
+                    .chain(),
+                (local_shooting, local_sub_weapon, activate_special)
+                    .chain()
+                    .run_if(in_state(MatchPhase::InProgress)),
+                (resolve_shots, resolve_sub_weapon_throws, resolve_specials).chain(),
+                apply_deferred,
+                projectile_hits,
+                apply_deferred,
+                (paint_system, update_bombs).chain(),
+                (
+                    (
+                        rainmaker_shield_hits,
+                        pick_up_rainmaker,
+                        resolve_rainmaker_pickups,
+                    )
+                        .chain()
+                        .run_if(resource_equals(GameMode::Rainmaker)),
+                    (
+                        (
+                            update_coop_waves,
+                            move_enemies,
+                            defeat_enemies,
+                            send_coop_state_udp,
+                        )
+                            .chain()
+                            .run_if(is_match_host),
+                        apply_coop_snapshot.run_if(not(is_match_host)),
+                        enemy_attacks,
+                        collect_eggs,
+                        deposit_eggs,
+                    )
+                        .chain()
+                        .run_if(resource_equals(GameMode::CoopWaves)),
+                )
+                    .chain()
+                    .run_if(in_state(MatchPhase::InProgress)),
+                resolve_explosions,
+                splat_players,
+                charge_special_gauge,
+                (
+                    (update_splat_zones, send_splat_zones_state_udp)
+                        .chain()
+                        .run_if(resource_equals(GameMode::SplatZones))
+                        .run_if(is_match_host),
+                    (update_tower_control, send_tower_state_udp)
+                        .chain()
+                        .run_if(resource_equals(GameMode::TowerControl))
+                        .run_if(is_match_host),
+                    (
+                        drop_rainmaker_on_splat,
+                        follow_rainmaker_carrier,
+                        (update_rainmaker, send_rainmaker_state_udp)
+                            .chain()
+                            .run_if(is_match_host),
+                    )
+                        .chain()
+                        .run_if(resource_equals(GameMode::Rainmaker)),
+                    (lose_eggs_on_splat, resolve_egg_events, send_egg_events_udp)
+                        .chain()
+                        .run_if(resource_equals(GameMode::CoopWaves)),
+                )
+                    .chain()
+                    .run_if(in_state(MatchPhase::InProgress)),
+                (
+                    refill_ink,
+                    enemy_ink_damage,
+                    regenerate_health,
+                    respawn_players,
+                    respawn_remote_players,
+                    tick_spawn_protection,
+                    tick_ink_armor,
+                )
+                    .chain(),
+                // 制限時間もティックで数え、どの端末でも同じティックで試合が終わるようにする
+                (
+                    tick_countdown.run_if(in_state(MatchPhase::Countdown)),
+                    tick_match_timer.run_if(in_state(MatchPhase::InProgress)),
+                    tick_results.run_if(in_state(MatchPhase::Finished)),
+                    send_match_state_udp.run_if(is_match_host),
+                )
+                    .chain(),
+                (
+                    send_player_position_udp,
+                    send_shoot_action_udp,
+                    send_sub_weapon_udp,
+                    send_special_udp,
+                    send_rainmaker_pickup_udp,
+                    send_combat_events_udp,
+                )
+                    .chain(),
+                (record_simulated_positions, clear_input_edges).chain(),
+            )
+                .chain()
+                .run_if(in_state(EditorState::Off)),
+        )
+        .add_systems(
+            Update,
+            (
+                (interpolate_simulated_positions, camera_follow).chain(),
+                update_squid_sprite,
+                fade_beams,
+                sync_remote_players,
                 monitor_connection,
                 send_test_requests,
-            ),
+            )
+                .run_if(in_state(EditorState::Off)),
         )
         .run();
 }
 
-fn setup(mut commands: Commands) {
-    // カメラ
-    commands.spawn(Camera2dBundle::default());
+fn setup(
+    mut commands: Commands,
+    network_client: Res<NetworkClient>,
+    arena: Res<Arena>,
+    weapon_registry: Res<WeaponRegistry>,
+    sub_weapon_registry: Res<SubWeaponRegistry>,
+) {
+    let spawn = arena.spawn_point(Team::Blue);
 
     // テスト操作の説明をログに出力
     info!("🎮 Game Controls:");
     info!("  WASD/Arrow Keys - Move player");
-    info!("  Mouse Click - Shoot");
+    info!("  Mouse Left (hold) - Shoot");
+    info!("  Shift (hold) - Squid form");
+    info!("  Mouse Right - Throw sub weapon");
+    info!("  E - Special (ink armor) when the gauge is full");
+    info!("  Q - Switch weapon");
+    info!("  Enter - Ready up in the lobby");
+    info!("  O - Toggle offline practice in the lobby");
+    info!("  Mouse Left (hold, release) - Charged blast while carrying the rainmaker");
+    info!("  F1 - Toggle map editor");
+    info!("🎮 Gamepad (2P with --split-screen):");
+    info!("  Left Stick - Move, Right Stick - Aim");
+    info!("  RT - Shoot, LT - Squid form, RB - Sub weapon, Y - Special, X - Switch weapon");
     info!("🧪 Test Commands:");
     info!("  T - Send test message to server");
     info!("  P - Ping server");
@@ -57,39 +354,19 @@ fn setup(mut commands: Commands) {
     info!("  G - Request game state");
 
     // プレイヤー
+    let player = spawn_player(
+        &mut commands,
+        network_client.player_id,
+        Team::Blue,
+        spawn,
+        DEFAULT_WEAPON,
+        &weapon_registry,
+        &sub_weapon_registry,
+    );
+
+    // カメラ
     commands.spawn((
-        Player {
-            id: uuid::Uuid::new_v4(),
-        },
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::BLUE,
-                custom_size: Some(Vec2::new(30.0, 30.0)),
-                ..default()
-            },
-            transform: Transform::from_xyz(0.0, 0.0, 1.0),
-            ..default()
-        },
-        Velocity(Vec2::ZERO),
-        Health(100),
+        Camera2dBundle::default(),
+        components::PlayerCamera { player },
     ));
-
-    // 地面（ペイント可能エリア）
-    for x in -20..20 {
-        for y in -15..15 {
-            commands.spawn((
-                PaintableGround,
-                SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::WHITE,
-                        custom_size: Some(Vec2::new(32.0, 32.0)),
-                        ..default()
-                    },
-                    transform: Transform::from_xyz(x as f32 * 32.0, y as f32 * 32.0, 0.0),
-                    ..default()
-                },
-                PaintColor(Color::WHITE),
-            ));
-        }
-    }
 }
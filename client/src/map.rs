@@ -0,0 +1,2 @@
+// マップファイルの型はサーバーと共有できるよう splatoon_map クレートに置いている
+pub use splatoon_map::*;
@@ -0,0 +1,170 @@
+use crate::arena::WALL_COLOR;
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+use serde_json::json;
+
+// ロビーで Enter を押すと準備完了を送る。カウントダウンはホスト（オフラインなら自分）が始め、
+// ホスト以外は match_state が届くまで待つ
+pub fn lobby_ready(
+    keyboard_input: Res<Input<KeyCode>>,
+    network_client: Res<NetworkClient>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+
+    // 分割画面の 2P やボットも別のクライアントとして準備完了にする
+    let senders = network_client
+        .sender
+        .iter()
+        .map(|sender| (network_client.player_id, sender))
+        .chain(
+            network_client
+                .extra_senders
+                .iter()
+                .map(|(id, sender)| (*id, sender)),
+        );
+    for (player_id, sender) in senders {
+        let message = json!({
+            "type": "player_ready",
+            "player_id": player_id.to_string()
+        });
+
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send ready message: {}", e);
+        }
+    }
+
+    if network_client.is_host() {
+        next_phase.set(MatchPhase::Countdown);
+    }
+}
+
+// --mode <name> で遊ぶルールを選ぶ
+pub fn select_game_mode_from_args(mut game_mode: ResMut<GameMode>) {
+    let args = std::env::args().collect::<Vec<_>>();
+    let Some(name) = args
+        .iter()
+        .position(|arg| arg == "--mode")
+        .and_then(|index| args.get(index + 1))
+    else {
+        return;
+    };
+
+    match GameMode::from_name(name) {
+        Some(mode) => {
+            info!("🎯 Game mode: {:?}", mode);
+            *game_mode = mode;
+        }
+        None => warn!("Unknown game mode: {}", name),
+    }
+}
+
+// --offline でサーバーに繋がずに始める
+pub fn select_offline_from_args(mut network_client: ResMut<NetworkClient>) {
+    if std::env::args().any(|arg| arg == "--offline") {
+        info!("📴 Offline practice mode");
+        network_client.offline = true;
+    }
+}
+
+// --host で試合の進行とルールの状態を決めるクライアントになる
+pub fn select_host_from_args(mut network_client: ResMut<NetworkClient>) {
+    if std::env::args().any(|arg| arg == "--host") {
+        info!("👑 Hosting the match");
+        network_client.host = true;
+    }
+}
+
+pub fn is_match_host(network_client: Res<NetworkClient>) -> bool {
+    network_client.is_host()
+}
+
+pub fn reset_match_clock(mut clock: ResMut<MatchClock>) {
+    *clock = MatchClock::default();
+}
+
+// 新しい試合の開始時に地面を塗られていない状態へ戻す
+#[allow(clippy::type_complexity)]
+pub fn reset_turf(
+    mut game_state: ResMut<GameState>,
+    mut ground_query: Query<(&mut PaintColor, &mut Sprite), With<PaintableGround>>,
+    mut wall_query: Query<
+        (&mut PaintColor, &mut Sprite),
+        (With<PaintableWall>, Without<PaintableGround>),
+    >,
+) {
+    game_state.clear_turf();
+
+    for (mut paint_color, mut sprite) in ground_query.iter_mut() {
+        paint_color.0 = Color::WHITE;
+        sprite.color = Color::WHITE;
+    }
+
+    for (mut paint_color, mut sprite) in wall_query.iter_mut() {
+        paint_color.0 = WALL_COLOR;
+        sprite.color = WALL_COLOR;
+    }
+}
+
+pub fn tick_countdown(
+    time: Res<Time>,
+    mut clock: ResMut<MatchClock>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+) {
+    if clock.countdown.tick(time.delta()).just_finished() {
+        info!("🚩 Match started!");
+        next_phase.set(MatchPhase::InProgress);
+    }
+}
+
+pub fn tick_match_timer(
+    time: Res<Time>,
+    mut clock: ResMut<MatchClock>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+) {
+    if clock.match_timer.tick(time.delta()).just_finished() {
+        info!("🏁 Match finished!");
+        next_phase.set(MatchPhase::Finished);
+    }
+}
+
+// 試合終了時の塗り面積を集計する
+pub fn tally_results(game_state: Res<GameState>, mut result: ResMut<MatchResult>) {
+    let blue_percent = game_state.turf_percent(Team::Blue);
+    let orange_percent = game_state.turf_percent(Team::Orange);
+
+    let winner = if blue_percent > orange_percent {
+        Some(Team::Blue)
+    } else if orange_percent > blue_percent {
+        Some(Team::Orange)
+    } else {
+        None
+    };
+
+    *result = MatchResult {
+        winner,
+        scores: vec![
+            (Team::Blue, format!("{:.1}%", blue_percent)),
+            (Team::Orange, format!("{:.1}%", orange_percent)),
+        ],
+        ..default()
+    };
+
+    info!(
+        "📋 Results - Blue: {:.1}%, Orange: {:.1}%",
+        blue_percent, orange_percent
+    );
+}
+
+pub fn tick_results(
+    time: Res<Time>,
+    mut clock: ResMut<MatchClock>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+) {
+    if clock.results.tick(time.delta()).just_finished() {
+        next_phase.set(MatchPhase::Waiting);
+    }
+}
@@ -1,2 +1,6 @@
 // This file is deprecated - using UDP network instead
-// Kept for reference only
\ No newline at end of file
+// Kept for reference only
+
+// 旧プロトコルのメッセージ定義も参照用に残す
+#[allow(dead_code)]
+pub type LegacyGameMessage = crate::resources::GameMessage;
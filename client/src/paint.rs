@@ -0,0 +1,112 @@
+use crate::components::*;
+use crate::events::*;
+use crate::resources::*;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use uuid::Uuid;
+
+// タイルの塗り状態（GameState）と地面・壁スプライトの色をまとめて更新する
+#[derive(SystemParam)]
+#[allow(clippy::type_complexity)]
+pub struct GroundPainter<'w, 's> {
+    pub game_state: ResMut<'w, GameState>,
+    ground_tiles: Res<'w, GroundTiles>,
+    wall_tiles: Res<'w, WallTiles>,
+    arena: Res<'w, Arena>,
+    ground_query:
+        Query<'w, 's, (&'static mut PaintColor, &'static mut Sprite), With<PaintableGround>>,
+    wall_query: Query<
+        'w,
+        's,
+        (&'static mut PaintColor, &'static mut Sprite),
+        (With<PaintableWall>, Without<PaintableGround>),
+    >,
+    inked_events: EventWriter<'w, TurfInkedEvent>,
+}
+
+impl<'w, 's> GroundPainter<'w, 's> {
+    // 地面のないタイルや相手のリスポーン地点は塗れないので None。
+    // 塗れた場合は床にチームの色が新しく付いたかどうかを返す（壁はカバー率に数えないので false）
+    pub fn ink_tile(&mut self, tile: (i32, i32), color: Color) -> Option<bool> {
+        let team = Team::from_color(color);
+        if let Some(team) = team {
+            if self.arena.is_protected_from(tile, team) {
+                return None;
+            }
+        }
+
+        if self.wall_tiles.0.contains_key(&tile) {
+            return self.paint_wall(tile, color).then_some(false);
+        }
+
+        let entity = self.ground_tiles.0.get(&tile)?;
+        let (mut paint_color, mut sprite) = self.ground_query.get_mut(*entity).ok()?;
+
+        paint_color.0 = color;
+        sprite.color = color;
+        let changed = self.game_state.paint_tile(tile, color);
+        Some(changed && team.is_some())
+    }
+
+    pub fn paint_wall(&mut self, tile: (i32, i32), color: Color) -> bool {
+        let Some(entity) = self.wall_tiles.0.get(&tile) else {
+            return false;
+        };
+        let Ok((mut paint_color, mut sprite)) = self.wall_query.get_mut(*entity) else {
+            return false;
+        };
+
+        paint_color.0 = color;
+        sprite.color = color;
+        self.game_state.paint_wall(tile, color);
+        true
+    }
+
+    // 1タイル塗り、新しく塗れたら true
+    pub fn paint_at(&mut self, position: Vec2, color: Color) -> bool {
+        self.ink_tile(world_to_tile(position), color) == Some(true)
+    }
+
+    // 中心から半径 radius（ワールド座標）以内のタイルを塗り、新しく塗れた枚数を返す
+    pub fn paint_circle(&mut self, center: Vec2, radius: f32, color: Color) -> usize {
+        let (cx, cy) = world_to_tile(center);
+        let reach = (radius / TILE_SIZE).ceil() as i32;
+        let mut inked = 0;
+
+        for x in cx - reach..=cx + reach {
+            for y in cy - reach..=cy + reach {
+                let tile_center = Vec2::new(x as f32, y as f32) * TILE_SIZE;
+                if tile_center.distance(center) <= radius
+                    && self.ink_tile((x, y), color) == Some(true)
+                {
+                    inked += 1;
+                }
+            }
+        }
+        inked
+    }
+
+    // start から end まで半タイルごとに塗る。radius が 0 なら線上のタイルだけ
+    pub fn paint_line(&mut self, start: Vec2, end: Vec2, radius: f32, color: Color) -> usize {
+        let length = start.distance(end);
+        let steps = (length / (TILE_SIZE / 2.0)).ceil().max(1.0) as i32;
+        let mut inked = 0;
+
+        for step in 0..=steps {
+            let point = start.lerp(end, step as f32 / steps as f32);
+            if radius > 0.0 {
+                inked += self.paint_circle(point, radius, color);
+            } else if self.paint_at(point, color) {
+                inked += 1;
+            }
+        }
+        inked
+    }
+
+    // 塗った枚数をプレイヤーのスペシャルゲージに加算させる
+    pub fn credit(&mut self, player: Uuid, tiles: usize) {
+        if tiles > 0 {
+            self.inked_events.send(TurfInkedEvent { player, tiles });
+        }
+    }
+}
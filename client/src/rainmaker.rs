@@ -0,0 +1,349 @@
+use crate::components::*;
+use crate::events::*;
+use crate::map::*;
+use crate::paint::*;
+use crate::resources::*;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+const SHIELD_COLOR: Color = Color::rgba(1.0, 0.9, 0.2, 0.8);
+
+// 目印の範囲の中心（ワールド座標）
+fn objective_center(objective: &ObjectiveDef) -> Vec2 {
+    let size = Vec2::new(objective.size.0 as f32 - 1.0, objective.size.1 as f32 - 1.0);
+    tile_to_world(objective.position) + size * TILE_SIZE / 2.0
+}
+
+// ガチホコを元の武器に持ち替える
+fn restore_weapon(
+    commands: &mut Commands,
+    entity: Entity,
+    weapon: &mut Weapon,
+    carrier: &RainmakerCarrier,
+    weapon_registry: &WeaponRegistry,
+) {
+    weapon.id = carrier.previous_weapon.clone();
+    weapon.stats = weapon_registry.get(&weapon.id);
+    commands.entity(entity).remove::<RainmakerCarrier>();
+}
+
+// 試合開始時に Rainmaker の目印（なければマップの中央）へシールド付きのガチホコを置く。
+// Goal の目印の team はそのゴールを守るチーム
+pub fn reset_rainmaker(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    weapon_registry: Res<WeaponRegistry>,
+    mut rainmaker: ResMut<Rainmaker>,
+    marker_query: Query<Entity, With<RainmakerMarker>>,
+    mut carrier_query: Query<(Entity, &mut Weapon, &RainmakerCarrier)>,
+) {
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (entity, mut weapon, carrier) in carrier_query.iter_mut() {
+        restore_weapon(
+            &mut commands,
+            entity,
+            &mut weapon,
+            carrier,
+            &weapon_registry,
+        );
+    }
+
+    let map = &arena.map;
+    let home = map
+        .objectives
+        .iter()
+        .find(|objective| objective.kind == ObjectiveKind::Rainmaker)
+        .map(objective_center)
+        .unwrap_or_else(|| {
+            warn!("Map {} has no rainmaker, using the center", map.name);
+            let size = Vec2::new(map.width() as f32 - 1.0, map.height() as f32 - 1.0);
+            tile_to_world(map.origin) + size * TILE_SIZE / 2.0
+        });
+
+    let mut goals = map
+        .objectives
+        .iter()
+        .filter(|objective| objective.kind == ObjectiveKind::Goal)
+        .filter_map(|objective| {
+            let team = Team::from_name(objective.team.as_deref()?)?;
+            Some((team, objective_center(objective)))
+        })
+        .collect::<HashMap<_, _>>();
+    for team in [Team::Blue, Team::Orange] {
+        goals.entry(team).or_insert_with(|| {
+            warn!(
+                "Map {} has no {} goal, using the spawn",
+                map.name,
+                team.name()
+            );
+            arena.spawn_point(team)
+        });
+    }
+
+    commands.spawn((
+        RainmakerMarker,
+        SpriteBundle {
+            sprite: Sprite {
+                color: SHIELD_COLOR,
+                custom_size: Some(Vec2::splat(RAINMAKER_RADIUS * 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(home.x, home.y, 0.9),
+            ..default()
+        },
+    ));
+    *rainmaker = Rainmaker::new(home, goals);
+}
+
+// シールドに当たった弾と爆発でシールドを削る。割れたら割ったチームの色で周りを塗る。
+// 弾はどのクライアントでも止めるが、シールドを削るのはホストだけで、他は rainmaker_state に合わせる
+pub fn rainmaker_shield_hits(
+    mut commands: Commands,
+    mut rainmaker: ResMut<Rainmaker>,
+    network_client: Res<NetworkClient>,
+    projectile_query: Query<(Entity, &Transform, &Projectile)>,
+    mut explosion_events: EventReader<ExplosionEvent>,
+    mut painter: GroundPainter,
+) {
+    if !rainmaker.shielded() {
+        explosion_events.clear();
+        return;
+    }
+
+    let center = rainmaker.position;
+    let mut hits = Vec::new();
+    for (entity, transform, projectile) in projectile_query.iter() {
+        let position = transform.translation.truncate();
+        if position.distance(center) <= RAINMAKER_RADIUS {
+            commands.entity(entity).despawn();
+            hits.push((
+                projectile.owner,
+                projectile.team,
+                projectile.damage_at(position),
+            ));
+        }
+    }
+    for explosion in explosion_events.read() {
+        if explosion.position.distance(center) <= explosion.radius + RAINMAKER_RADIUS {
+            hits.push((explosion.owner, explosion.team, explosion.damage));
+        }
+    }
+
+    if !network_client.is_host() {
+        return;
+    }
+
+    for (owner, team, damage) in hits {
+        if !rainmaker.damage_shield(damage) {
+            continue;
+        }
+
+        info!("💥 {} team broke the rainmaker shield!", team.name());
+        rainmaker.broken_by = Some(team);
+        let inked = painter.paint_circle(center, RAINMAKER_BURST_RADIUS, team.color());
+        painter.credit(owner, inked);
+    }
+}
+
+// シールドが割れて落ちているガチホコに触れたら拾う
+pub fn pick_up_rainmaker(
+    rainmaker: Res<Rainmaker>,
+    player_query: Query<(&Transform, &Player), Without<Splatted>>,
+    mut pickup_events: EventWriter<RainmakerPickedUpEvent>,
+) {
+    if rainmaker.shielded() || rainmaker.carrier.is_some() {
+        return;
+    }
+
+    for (transform, player) in player_query.iter() {
+        if transform
+            .translation
+            .truncate()
+            .distance(rainmaker.position)
+            <= RAINMAKER_RADIUS
+        {
+            pickup_events.send(RainmakerPickedUpEvent {
+                player: player.id,
+                team: player.team,
+            });
+            return;
+        }
+    }
+}
+
+// 拾ったプレイヤー（自分・他プレイヤーとも）を持ち主にする。自分ならガチホコに持ち替える
+pub fn resolve_rainmaker_pickups(
+    mut commands: Commands,
+    mut pickup_events: EventReader<RainmakerPickedUpEvent>,
+    mut rainmaker: ResMut<Rainmaker>,
+    weapon_registry: Res<WeaponRegistry>,
+    mut player_query: Query<(Entity, &Player, &mut Weapon), Without<RainmakerCarrier>>,
+) {
+    for event in pickup_events.read() {
+        // 先に拾った人が持つ
+        if rainmaker.shielded() || rainmaker.carrier.is_some() {
+            continue;
+        }
+        rainmaker.pick_up(event.player, event.team);
+        info!("🏆 {} team picked up the rainmaker", event.team.name());
+
+        for (entity, player, mut weapon) in player_query.iter_mut() {
+            if player.id != event.player {
+                continue;
+            }
+            commands.entity(entity).insert(RainmakerCarrier {
+                previous_weapon: weapon.id.clone(),
+            });
+            weapon.id = RAINMAKER_WEAPON.to_string();
+            weapon.stats = weapon_registry.get(RAINMAKER_WEAPON);
+        }
+    }
+}
+
+// 持ち主がやられたらその場に落とす
+pub fn drop_rainmaker_on_splat(
+    mut commands: Commands,
+    mut splat_events: EventReader<PlayerSplattedEvent>,
+    mut rainmaker: ResMut<Rainmaker>,
+    weapon_registry: Res<WeaponRegistry>,
+    mut carrier_query: Query<(Entity, &Player, &mut Weapon, &RainmakerCarrier)>,
+) {
+    for event in splat_events.read() {
+        if rainmaker.carrier.map(|(id, _)| id) != Some(event.victim) {
+            continue;
+        }
+        rainmaker.drop_at(event.position);
+        info!("💧 The rainmaker was dropped");
+
+        for (entity, player, mut weapon, carrier) in carrier_query.iter_mut() {
+            if player.id == event.victim {
+                restore_weapon(
+                    &mut commands,
+                    entity,
+                    &mut weapon,
+                    carrier,
+                    &weapon_registry,
+                );
+            }
+        }
+    }
+}
+
+// 持ち主について動かす。ホスト以外も同期の合間はこれで持ち主に合わせる
+pub fn follow_rainmaker_carrier(
+    mut rainmaker: ResMut<Rainmaker>,
+    player_query: Query<(&Transform, &Player)>,
+    remote_query: Query<(&Transform, &RemotePlayer)>,
+) {
+    let Some((carrier, _)) = rainmaker.carrier else {
+        return;
+    };
+
+    let local = player_query
+        .iter()
+        .map(|(transform, player)| (transform, player.id));
+    let remote = remote_query
+        .iter()
+        .map(|(transform, remote)| (transform, remote.id));
+    if let Some((transform, _)) = local.chain(remote).find(|(_, id)| *id == carrier) {
+        rainmaker.position = transform.translation.truncate();
+    }
+}
+
+// 落ちている間はシールドの張り直しを待ち、運んだ記録を更新する。相手のゴールに届いたら勝ち
+pub fn update_rainmaker(
+    mut rainmaker: ResMut<Rainmaker>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+    time: Res<Time>,
+) {
+    if rainmaker.carrier.is_none() {
+        if !rainmaker.shielded() && rainmaker.tick_reshield(time.delta()) {
+            info!("🛡️ The rainmaker shield came back");
+        }
+        return;
+    }
+
+    rainmaker.record_progress();
+
+    if let Some(team) = rainmaker.delivered_by() {
+        info!("💥 {} team delivered the rainmaker!", team.name());
+        next_phase.set(MatchPhase::Finished);
+    }
+}
+
+pub fn update_rainmaker_sprite(
+    rainmaker: Res<Rainmaker>,
+    mut marker_query: Query<(&mut Transform, &mut Sprite), With<RainmakerMarker>>,
+) {
+    if !rainmaker.is_changed() {
+        return;
+    }
+
+    // シールドがある間は大きく、削れるほど小さく表示する
+    let size = if rainmaker.shielded() {
+        RAINMAKER_RADIUS * (1.0 + rainmaker.shield as f32 / RAINMAKER_SHIELD_HP as f32)
+    } else {
+        RAINMAKER_RADIUS
+    };
+    let color = match rainmaker.carrier {
+        _ if rainmaker.shielded() => SHIELD_COLOR,
+        Some((_, team)) => team.color(),
+        None => Color::WHITE,
+    };
+
+    for (mut transform, mut sprite) in marker_query.iter_mut() {
+        transform.translation.x = rainmaker.position.x;
+        transform.translation.y = rainmaker.position.y;
+        sprite.custom_size = Some(Vec2::splat(size));
+        sprite.color = color;
+    }
+}
+
+pub fn tally_rainmaker(rainmaker: Res<Rainmaker>, mut result: ResMut<MatchResult>) {
+    let winner = rainmaker.delivered_by().or_else(|| rainmaker.leader());
+
+    *result = MatchResult {
+        winner,
+        scores: [Team::Blue, Team::Orange]
+            .into_iter()
+            .map(|team| {
+                (
+                    team,
+                    format!("{:.0}% carried", rainmaker.best(team) * 100.0),
+                )
+            })
+            .collect(),
+        ..default()
+    };
+}
+
+pub fn update_rainmaker_text(
+    rainmaker: Res<Rainmaker>,
+    mut text_query: Query<&mut Text, With<ModeScoreText>>,
+) {
+    let mut label = [Team::Blue, Team::Orange]
+        .into_iter()
+        .map(|team| {
+            format!(
+                "{} {:.0}%",
+                team.name().to_uppercase(),
+                rainmaker.best(team) * 100.0
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("   |   ");
+
+    if rainmaker.shielded() {
+        label.push_str(&format!("   SHIELD {}", rainmaker.shield));
+    } else if let Some((_, team)) = rainmaker.carrier {
+        label.push_str(&format!("   {} has the rainmaker", team.name()));
+    } else {
+        label.push_str("   RAINMAKER DROPPED");
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = label.clone();
+    }
+}
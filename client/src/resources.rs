@@ -1,3 +1,4 @@
+use crate::map::{MapDef, ObjectiveKind, TileKind};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -9,6 +10,67 @@ use uuid::Uuid;
 pub struct GameState {
     pub players: HashMap<Uuid, PlayerState>,
     pub painted_tiles: HashMap<(i32, i32), Color>,
+    // 壁の塗り状態。床とは別に持ち、カバー率には数えない
+    pub painted_walls: HashMap<(i32, i32), Color>,
+    pub team_tiles: HashMap<Team, usize>,
+    pub total_tiles: usize,
+}
+
+impl GameState {
+    // タイルを塗り、チームごとのタイル数を差分で更新する
+    // 色が変わったタイルなら true
+    pub fn paint_tile(&mut self, tile: (i32, i32), color: Color) -> bool {
+        let previous = self.painted_tiles.insert(tile, color);
+        if let Some(team) = previous.and_then(Team::from_color) {
+            if let Some(count) = self.team_tiles.get_mut(&team) {
+                *count = count.saturating_sub(1);
+            }
+        }
+
+        if let Some(team) = Team::from_color(color) {
+            *self.team_tiles.entry(team).or_insert(0) += 1;
+        }
+
+        previous != Some(color)
+    }
+
+    pub fn paint_wall(&mut self, tile: (i32, i32), color: Color) -> bool {
+        self.painted_walls.insert(tile, color) != Some(color)
+    }
+
+    // 指定位置のタイル（床または壁）を塗っているチーム
+    pub fn team_at(&self, position: Vec2) -> Option<Team> {
+        let tile = world_to_tile(position);
+        self.painted_tiles
+            .get(&tile)
+            .or_else(|| self.painted_walls.get(&tile))
+            .and_then(|color| Team::from_color(*color))
+    }
+
+    pub fn terrain_at(&self, position: Vec2, team: Team) -> Terrain {
+        match self.team_at(position) {
+            None => Terrain::Neutral,
+            Some(owner) if owner == team => Terrain::OwnInk,
+            Some(_) => Terrain::EnemyInk,
+        }
+    }
+
+    pub fn clear_turf(&mut self) {
+        self.painted_tiles.clear();
+        self.painted_walls.clear();
+        self.team_tiles.clear();
+    }
+
+    pub fn count_turf(&self, team: Team) -> usize {
+        self.team_tiles.get(&team).copied().unwrap_or(0)
+    }
+
+    pub fn turf_percent(&self, team: Team) -> f32 {
+        if self.total_tiles == 0 {
+            return 0.0;
+        }
+        self.count_turf(team) as f32 / self.total_tiles as f32 * 100.0
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -17,20 +79,1344 @@ pub struct PlayerState {
     pub position: Vec2,
     pub health: i32,
     pub team: Team,
+    #[serde(default)]
+    pub submerged: bool,
+    // 最後に受け取った player_update の送り主のティック
+    #[serde(default)]
+    pub tick: u64,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Team {
     Blue,
     Orange,
 }
 
+impl Team {
+    pub fn color(self) -> Color {
+        match self {
+            Team::Blue => Color::BLUE,
+            Team::Orange => Color::ORANGE,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Team> {
+        match name {
+            "blue" => Some(Team::Blue),
+            "orange" => Some(Team::Orange),
+            _ => None,
+        }
+    }
+
+    pub fn from_color(color: Color) -> Option<Team> {
+        [Team::Blue, Team::Orange]
+            .into_iter()
+            .find(|team| team.color() == color)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Team::Blue => "blue",
+            Team::Orange => "orange",
+        }
+    }
+
+    pub fn opponent(self) -> Team {
+        match self {
+            Team::Blue => Team::Orange,
+            Team::Orange => Team::Blue,
+        }
+    }
+}
+
+// プレイヤーから見た足元のインク
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Terrain {
+    Neutral,
+    OwnInk,
+    EnemyInk,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct TerrainSpeeds {
+    pub neutral: f32,
+    pub own_ink: f32,
+    pub enemy_ink: f32,
+}
+
+impl TerrainSpeeds {
+    pub fn multiplier(&self, terrain: Terrain) -> f32 {
+        match terrain {
+            Terrain::Neutral => self.neutral,
+            Terrain::OwnInk => self.own_ink,
+            Terrain::EnemyInk => self.enemy_ink,
+        }
+    }
+}
+
+pub const TERRAIN_CONFIG_PATH: &str = "assets/config/terrain.ron";
+
+// 足元のインクによる移動速度の倍率。assets/config/terrain.ron で調整する
+#[derive(Resource, Deserialize, Clone)]
+pub struct TerrainModifiers {
+    pub base_speed: f32,
+    pub humanoid: TerrainSpeeds,
+    pub squid: TerrainSpeeds,
+    pub enemy_ink_damage_per_second: f32,
+    pub enemy_ink_health_floor: i32,
+}
+
+impl Default for TerrainModifiers {
+    fn default() -> Self {
+        Self {
+            base_speed: 200.0,
+            humanoid: TerrainSpeeds {
+                neutral: 1.0,
+                own_ink: 1.1,
+                enemy_ink: 0.4,
+            },
+            squid: TerrainSpeeds {
+                neutral: 0.3,
+                own_ink: 1.7,
+                enemy_ink: 0.2,
+            },
+            enemy_ink_damage_per_second: 12.0,
+            enemy_ink_health_floor: 40,
+        }
+    }
+}
+
+impl TerrainModifiers {
+    pub fn speed(&self, terrain: Terrain, squid: bool) -> f32 {
+        let speeds = if squid { &self.squid } else { &self.humanoid };
+        self.base_speed * speeds.multiplier(terrain)
+    }
+}
+
+pub const WEAPONS_DIR: &str = "assets/weapons";
+pub const DEFAULT_WEAPON: &str = "splattershot";
+
+// メイン武器の種類ごとの撃ち方
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeaponKind {
+    // 弾を連射する
+    #[default]
+    Shooter,
+    // 移動しながら幅広く塗る
+    Roller,
+    // 溜めて直線上を一気に撃ち抜く
+    Charger,
+    // 射程の先で爆発する弾を撃つ
+    Blaster,
+}
+
+impl WeaponKind {
+    // 種類ごとに異なるネットワークメッセージの type
+    pub fn message_type(self) -> &'static str {
+        match self {
+            WeaponKind::Shooter => "player_shoot",
+            WeaponKind::Roller => "player_roll",
+            WeaponKind::Charger => "charger_shot",
+            WeaponKind::Blaster => "blaster_shot",
+        }
+    }
+
+    pub fn from_message_type(message_type: &str) -> Option<WeaponKind> {
+        [
+            WeaponKind::Shooter,
+            WeaponKind::Roller,
+            WeaponKind::Charger,
+            WeaponKind::Blaster,
+        ]
+        .into_iter()
+        .find(|kind| kind.message_type() == message_type)
+    }
+}
+
+// メイン武器の性能。assets/weapons/<id>.ron に1武器ずつ定義する
+#[derive(Deserialize, Clone, Debug)]
+pub struct WeaponDef {
+    pub name: String,
+    #[serde(default)]
+    pub kind: WeaponKind,
+    pub fire_rate: f32,
+    pub damage: i32,
+    pub range: f32,
+    pub spread: f32,
+    pub projectile_speed: f32,
+    pub projectile_size: f32,
+    pub paint_radius: f32,
+    pub ink_cost: f32,
+    // 最大まで溜めるのにかかる秒数。チャージャー以外でも 0 より大きければ溜めて撃つ
+    #[serde(default)]
+    pub charge_time: f32,
+    // ローラーが一度に塗る幅
+    #[serde(default)]
+    pub roll_width: f32,
+    // ブラスターの爆発半径
+    #[serde(default)]
+    pub explosion_radius: f32,
+    // ガチホコなどルール専用の武器。Q キーの切り替えには出てこない
+    #[serde(default)]
+    pub objective_only: bool,
+}
+
+impl Default for WeaponDef {
+    fn default() -> Self {
+        Self {
+            name: "Splattershot".to_string(),
+            kind: WeaponKind::Shooter,
+            fire_rate: 7.5,
+            damage: 36,
+            range: 320.0,
+            spread: 6.0,
+            projectile_speed: 640.0,
+            projectile_size: 8.0,
+            paint_radius: 24.0,
+            ink_cost: 0.9,
+            charge_time: 0.0,
+            roll_width: 0.0,
+            explosion_radius: 0.0,
+            objective_only: false,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct WeaponRegistry {
+    pub weapons: HashMap<String, WeaponDef>,
+}
+
+impl WeaponRegistry {
+    pub fn get(&self, id: &str) -> WeaponDef {
+        self.weapons.get(id).cloned().unwrap_or_default()
+    }
+
+    // ID 順で次の武器
+    pub fn next_after(&self, id: &str) -> Option<&str> {
+        let mut ids = self
+            .weapons
+            .iter()
+            .filter(|(_, weapon)| !weapon.objective_only)
+            .map(|(id, _)| id.as_str())
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        let index = ids
+            .iter()
+            .position(|other| *other == id)
+            .map_or(0, |i| i + 1);
+        ids.get(index % ids.len().max(1)).copied()
+    }
+}
+
+pub const SUB_WEAPONS_DIR: &str = "assets/sub_weapons";
+pub const DEFAULT_SUB_WEAPON: &str = "splat_bomb";
+
+// サブウェポン（投げるボム）の性能。assets/sub_weapons/<id>.ron に定義する
+#[derive(Deserialize, Clone, Debug)]
+pub struct SubWeaponDef {
+    pub name: String,
+    pub ink_cost: f32,
+    pub throw_range: f32,
+    // 投げてから着地するまでの秒数
+    pub flight_time: f32,
+    // 着地してから爆発するまでの秒数
+    pub fuse_time: f32,
+    pub explosion_radius: f32,
+    pub damage: i32,
+}
+
+impl Default for SubWeaponDef {
+    fn default() -> Self {
+        Self {
+            name: "Splat Bomb".to_string(),
+            ink_cost: 70.0,
+            throw_range: 256.0,
+            flight_time: 0.6,
+            fuse_time: 1.0,
+            explosion_radius: 96.0,
+            damage: 180,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SubWeaponRegistry {
+    pub sub_weapons: HashMap<String, SubWeaponDef>,
+}
+
+impl SubWeaponRegistry {
+    pub fn get(&self, id: &str) -> SubWeaponDef {
+        self.sub_weapons.get(id).cloned().unwrap_or_default()
+    }
+}
+
+// 地面タイル1枚のワールド座標上のサイズ
+pub const TILE_SIZE: f32 = 32.0;
+
+pub fn world_to_tile(position: Vec2) -> (i32, i32) {
+    (
+        (position.x / TILE_SIZE).round() as i32,
+        (position.y / TILE_SIZE).round() as i32,
+    )
+}
+
+pub fn tile_to_world(tile: (i32, i32)) -> Vec2 {
+    Vec2::new(tile.0 as f32, tile.1 as f32) * TILE_SIZE
+}
+
+pub const COUNTDOWN_SECONDS: f32 = 3.0;
+pub const MATCH_SECONDS: f32 = 180.0;
+pub const RESULTS_SECONDS: f32 = 10.0;
+
+// 試合の進行状態
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MatchPhase {
+    #[default]
+    Waiting,
+    Countdown,
+    InProgress,
+    Finished,
+}
+
+impl MatchPhase {
+    pub fn from_name(name: &str) -> Option<MatchPhase> {
+        match name {
+            "waiting" => Some(MatchPhase::Waiting),
+            "countdown" => Some(MatchPhase::Countdown),
+            "in_progress" => Some(MatchPhase::InProgress),
+            "finished" => Some(MatchPhase::Finished),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchPhase::Waiting => "waiting",
+            MatchPhase::Countdown => "countdown",
+            MatchPhase::InProgress => "in_progress",
+            MatchPhase::Finished => "finished",
+        }
+    }
+}
+
+// ホストが試合の進行を送る間隔
+pub const MATCH_SYNC_SECONDS: f32 = 1.0;
+// ホストがルール（ガチエリアなど）の状態を送る間隔
+pub const RULE_SYNC_SECONDS: f32 = 0.2;
+
+#[derive(Resource)]
+pub struct MatchClock {
+    pub countdown: Timer,
+    pub match_timer: Timer,
+    pub results: Timer,
+    pub sync: Timer,
+    pub rule_sync: Timer,
+}
+
+impl Default for MatchClock {
+    fn default() -> Self {
+        Self {
+            countdown: Timer::from_seconds(COUNTDOWN_SECONDS, TimerMode::Once),
+            match_timer: Timer::from_seconds(MATCH_SECONDS, TimerMode::Once),
+            results: Timer::from_seconds(RESULTS_SECONDS, TimerMode::Once),
+            sync: Timer::from_seconds(MATCH_SYNC_SECONDS, TimerMode::Repeating),
+            rule_sync: Timer::from_seconds(RULE_SYNC_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl MatchClock {
+    pub fn remaining(&self, phase: MatchPhase) -> f32 {
+        match phase {
+            MatchPhase::Countdown => self.countdown.remaining_secs(),
+            MatchPhase::InProgress => self.match_timer.remaining_secs(),
+            MatchPhase::Finished => self.results.remaining_secs(),
+            MatchPhase::Waiting => 0.0,
+        }
+    }
+}
+
+impl MatchClock {
+    // サーバーから受け取った残り時間に合わせる
+    pub fn sync_remaining(&mut self, phase: MatchPhase, remaining: f32) {
+        let timer = match phase {
+            MatchPhase::Countdown => &mut self.countdown,
+            MatchPhase::InProgress => &mut self.match_timer,
+            MatchPhase::Finished => &mut self.results,
+            MatchPhase::Waiting => return,
+        };
+        let duration = timer.duration().as_secs_f32();
+        let elapsed = (duration - remaining).clamp(0.0, duration);
+        timer.set_elapsed(std::time::Duration::from_secs_f32(elapsed));
+    }
+}
+
+pub const KILL_FEED_SECONDS: f32 = 4.0;
+
+// 画面右上に一定時間表示する命中・撃破のログ
+#[derive(Resource, Default)]
+pub struct KillFeed {
+    pub entries: Vec<(String, Timer)>,
+}
+
+impl KillFeed {
+    pub fn push(&mut self, message: String) {
+        self.entries.push((
+            message,
+            Timer::from_seconds(KILL_FEED_SECONDS, TimerMode::Once),
+        ));
+    }
+}
+
+// 結果画面に出す勝敗とチームごとの成績
+#[derive(Resource, Default)]
+pub struct MatchResult {
+    pub winner: Option<Team>,
+    pub scores: Vec<(Team, String)>,
+    // 勝敗の代わりに出す見出し（協力モードなど）
+    pub headline: Option<String>,
+}
+
+// ルール。--mode で選ぶか、サーバーの match_state に合わせる
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    #[default]
+    TurfWar,
+    SplatZones,
+    TowerControl,
+    Rainmaker,
+    CoopWaves,
+}
+
+impl GameMode {
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        match name {
+            "turf_war" => Some(GameMode::TurfWar),
+            "splat_zones" => Some(GameMode::SplatZones),
+            "tower_control" => Some(GameMode::TowerControl),
+            "rainmaker" => Some(GameMode::Rainmaker),
+            "coop" => Some(GameMode::CoopWaves),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::TurfWar => "turf_war",
+            GameMode::SplatZones => "splat_zones",
+            GameMode::TowerControl => "tower_control",
+            GameMode::Rainmaker => "rainmaker",
+            GameMode::CoopWaves => "coop",
+        }
+    }
+}
+
+// ガチエリア。エリアのタイルをこの割合以上塗ったチームが確保する
+pub const ZONE_CONTROL_PERCENT: f32 = 70.0;
+pub const ZONE_COUNT_SECONDS: f32 = 100.0;
+// 確保を奪われたチームには、確保中に進めたカウントのこの割合がペナルティとして付く
+pub const ZONE_PENALTY_RATIO: f32 = 0.75;
+pub const ZONE_PENALTY_MAX_SECONDS: f32 = 20.0;
+
+pub struct Zone {
+    // マップの objectives の何番目か
+    pub objective: usize,
+    pub tiles: Vec<(i32, i32)>,
+    pub owner: Option<Team>,
+}
+
+#[derive(Resource, Default)]
+pub struct SplatZones {
+    pub zones: Vec<Zone>,
+    // すべてのエリアを確保しているチーム
+    pub controller: Option<Team>,
+    pub counts: HashMap<Team, f32>,
+    pub penalties: HashMap<Team, f32>,
+    // 今の確保中に進めたカウント
+    pub counted: f32,
+}
+
+impl SplatZones {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        Self {
+            zones,
+            controller: None,
+            counts: HashMap::from([
+                (Team::Blue, ZONE_COUNT_SECONDS),
+                (Team::Orange, ZONE_COUNT_SECONDS),
+            ]),
+            penalties: HashMap::new(),
+            counted: 0.0,
+        }
+    }
+
+    pub fn count(&self, team: Team) -> f32 {
+        self.counts
+            .get(&team)
+            .copied()
+            .unwrap_or(ZONE_COUNT_SECONDS)
+    }
+
+    pub fn penalty(&self, team: Team) -> f32 {
+        self.penalties.get(&team).copied().unwrap_or(0.0)
+    }
+
+    // 全エリアの持ち主が同じならそのチームが確保している
+    pub fn full_control(&self) -> Option<Team> {
+        let owner = self.zones.first()?.owner?;
+        self.zones
+            .iter()
+            .all(|zone| zone.owner == Some(owner))
+            .then_some(owner)
+    }
+
+    // 確保しているチームが変わったら、奪われた側にペナルティを付ける
+    pub fn set_controller(&mut self, controller: Option<Team>) {
+        if controller == self.controller {
+            return;
+        }
+
+        if let Some(previous) = self.controller {
+            let penalty = (self.counted * ZONE_PENALTY_RATIO).min(ZONE_PENALTY_MAX_SECONDS);
+            *self.penalties.entry(previous).or_insert(0.0) += penalty;
+        }
+        self.controller = controller;
+        self.counted = 0.0;
+    }
+
+    // 確保中はペナルティを先に消化してからカウントを進める
+    pub fn tick(&mut self, seconds: f32) {
+        let Some(team) = self.controller else {
+            return;
+        };
+
+        let penalty = self.penalties.entry(team).or_insert(0.0);
+        if *penalty > 0.0 {
+            *penalty = (*penalty - seconds).max(0.0);
+            return;
+        }
+
+        let count = self.counts.entry(team).or_insert(ZONE_COUNT_SECONDS);
+        *count = (*count - seconds).max(0.0);
+        self.counted += seconds;
+    }
+
+    // カウントの少ないほうが勝ち
+    pub fn leader(&self) -> Option<Team> {
+        let blue = self.count(Team::Blue);
+        let orange = self.count(Team::Orange);
+        if blue < orange {
+            Some(Team::Blue)
+        } else if orange < blue {
+            Some(Team::Orange)
+        } else {
+            None
+        }
+    }
+}
+
+// ガチヤグラ。乗っているのが片方のチームだけのとき、相手の陣地へ進む
+pub const TOWER_SPEED: f32 = 48.0;
+pub const TOWER_RIDE_RADIUS: f32 = 40.0;
+pub const TOWER_CHECKPOINT_SECONDS: f32 = 3.0;
+
+#[derive(Resource, Default)]
+pub struct TowerControl {
+    // 通り道（ワールド座標）。blue は終点へ、orange は始点へ向かって進める
+    pub path: Vec<Vec2>,
+    pub length: f32,
+    // 始点からの距離
+    pub progress: f32,
+    pub checkpoints: Vec<f32>,
+    pub cleared: Vec<bool>,
+    // チェックポイントで止まっているチームと残り秒数。相手に乗り換えられたら消える
+    pub checkpoint_wait: Option<(Team, f32)>,
+    pub controller: Option<Team>,
+    pub contested: bool,
+    // 中央から相手の陣地側へ進めた最大距離
+    pub best: HashMap<Team, f32>,
+}
+
+impl TowerControl {
+    pub fn new(path: Vec<Vec2>) -> Self {
+        let length = path.windows(2).map(|pair| pair[0].distance(pair[1])).sum();
+        Self {
+            path,
+            length,
+            progress: length / 2.0,
+            checkpoints: Vec::new(),
+            cleared: Vec::new(),
+            checkpoint_wait: None,
+            controller: None,
+            contested: false,
+            best: HashMap::new(),
+        }
+    }
+
+    pub fn set_checkpoints(&mut self, checkpoints: Vec<f32>) {
+        self.cleared = vec![false; checkpoints.len()];
+        self.checkpoints = checkpoints;
+    }
+
+    fn direction(team: Team) -> f32 {
+        match team {
+            Team::Blue => 1.0,
+            Team::Orange => -1.0,
+        }
+    }
+
+    pub fn point_at(&self, distance: f32) -> Vec2 {
+        let mut remaining = distance;
+        for pair in self.path.windows(2) {
+            let segment = pair[0].distance(pair[1]);
+            if remaining <= segment {
+                return pair[0].lerp(pair[1], remaining / segment.max(f32::EPSILON));
+            }
+            remaining -= segment;
+        }
+        self.path.last().copied().unwrap_or(Vec2::ZERO)
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.point_at(self.progress)
+    }
+
+    // そのチームが中央から相手側へどれだけ進めているか
+    pub fn advance_of(&self, team: Team) -> f32 {
+        (self.progress - self.length / 2.0) * Self::direction(team)
+    }
+
+    pub fn best(&self, team: Team) -> f32 {
+        self.best.get(&team).copied().unwrap_or(0.0)
+    }
+
+    // team が seconds 秒だけヤグラを進める。相手側のチェックポイントでは一定時間止まる
+    pub fn push(&mut self, team: Team, mut seconds: f32) {
+        if let Some((waiting, remaining)) = self.checkpoint_wait.as_mut() {
+            if *waiting == team {
+                *remaining -= seconds;
+                if *remaining > 0.0 {
+                    return;
+                }
+                // 待ち終えた後の分だけ進む
+                seconds = -*remaining;
+            }
+            self.checkpoint_wait = None;
+        }
+
+        let direction = Self::direction(team);
+        let center = self.length / 2.0;
+        let mut next = (self.progress + direction * TOWER_SPEED * seconds).clamp(0.0, self.length);
+
+        for (checkpoint, cleared) in self.checkpoints.iter().zip(self.cleared.iter_mut()) {
+            let ahead = (checkpoint - self.progress) * direction;
+            let reach = (next - self.progress) * direction;
+            let enemy_side = (checkpoint - center) * direction > 0.0;
+            if !*cleared && enemy_side && ahead >= 0.0 && ahead <= reach {
+                next = *checkpoint;
+                *cleared = true;
+                self.checkpoint_wait = Some((team, TOWER_CHECKPOINT_SECONDS));
+                break;
+            }
+        }
+
+        self.progress = next;
+        let advance = self.advance_of(team);
+        let best = self.best.entry(team).or_insert(0.0);
+        *best = best.max(advance);
+    }
+
+    // 相手の陣地の端まで運んだチーム
+    pub fn finished_by(&self) -> Option<Team> {
+        if self.length <= 0.0 {
+            None
+        } else if self.progress >= self.length {
+            Some(Team::Blue)
+        } else if self.progress <= 0.0 {
+            Some(Team::Orange)
+        } else {
+            None
+        }
+    }
+
+    // 時間切れのときは相手側へ最も進めたチームの勝ち
+    pub fn leader(&self) -> Option<Team> {
+        let blue = self.best(Team::Blue);
+        let orange = self.best(Team::Orange);
+        if blue > orange {
+            Some(Team::Blue)
+        } else if orange > blue {
+            Some(Team::Orange)
+        } else {
+            None
+        }
+    }
+}
+
+// ガチホコ。シールドを割ると持てるようになり、相手のゴールまで運べば勝ち
+pub const RAINMAKER_WEAPON: &str = "rainmaker";
+pub const RAINMAKER_SHIELD_HP: i32 = 1000;
+// シールドの当たり判定と、拾える距離
+pub const RAINMAKER_RADIUS: f32 = 32.0;
+// シールドが割れたときに割ったチームの色で塗る半径
+pub const RAINMAKER_BURST_RADIUS: f32 = 128.0;
+// ゴールの目印の中心からこの距離まで運べば勝ち
+pub const RAINMAKER_GOAL_RADIUS: f32 = 48.0;
+// 持っている人の移動速度の倍率
+pub const RAINMAKER_CARRIER_SPEED: f32 = 0.7;
+// 誰も持たずに落ちているとこの秒数でシールドが張り直される
+pub const RAINMAKER_RESHIELD_SECONDS: f32 = 10.0;
+
+#[derive(Resource, Default)]
+pub struct Rainmaker {
+    pub home: Vec2,
+    pub position: Vec2,
+    pub shield: i32,
+    pub carrier: Option<(Uuid, Team)>,
+    pub reshield: Option<Timer>,
+    // チーム → そのチームが守るゴール。相手はここへ運ぶ
+    pub goals: HashMap<Team, Vec2>,
+    // 相手のゴールへ近づけた最大の割合（0.0〜1.0）
+    pub best: HashMap<Team, f32>,
+    // 最後にシールドを割ったチーム。ホスト以外は割れたときの塗りをこれで再現する
+    pub broken_by: Option<Team>,
+}
+
+impl Rainmaker {
+    pub fn new(home: Vec2, goals: HashMap<Team, Vec2>) -> Self {
+        Self {
+            home,
+            position: home,
+            shield: RAINMAKER_SHIELD_HP,
+            carrier: None,
+            reshield: None,
+            goals,
+            best: HashMap::new(),
+            broken_by: None,
+        }
+    }
+
+    pub fn shielded(&self) -> bool {
+        self.shield > 0
+    }
+
+    // シールドを削る。このダメージで割れたら true
+    pub fn damage_shield(&mut self, damage: i32) -> bool {
+        if !self.shielded() {
+            return false;
+        }
+        self.shield -= damage;
+        if self.shielded() {
+            return false;
+        }
+        self.shield = 0;
+        self.reshield = Some(Timer::from_seconds(
+            RAINMAKER_RESHIELD_SECONDS,
+            TimerMode::Once,
+        ));
+        true
+    }
+
+    pub fn pick_up(&mut self, player: Uuid, team: Team) {
+        self.carrier = Some((player, team));
+        self.reshield = None;
+    }
+
+    pub fn drop_at(&mut self, position: Vec2) {
+        self.carrier = None;
+        self.position = position;
+        self.reshield = Some(Timer::from_seconds(
+            RAINMAKER_RESHIELD_SECONDS,
+            TimerMode::Once,
+        ));
+    }
+
+    // 落ちている間だけ時間を進め、張り直されたら true
+    pub fn tick_reshield(&mut self, delta: std::time::Duration) -> bool {
+        let Some(reshield) = self.reshield.as_mut() else {
+            return false;
+        };
+        if !reshield.tick(delta).finished() {
+            return false;
+        }
+        self.reshield = None;
+        self.shield = RAINMAKER_SHIELD_HP;
+        true
+    }
+
+    pub fn enemy_goal(&self, team: Team) -> Option<Vec2> {
+        self.goals.get(&team.opponent()).copied()
+    }
+
+    // スタート地点から相手のゴールまでのうち、今どれだけ近づいているか
+    pub fn progress_of(&self, team: Team) -> f32 {
+        let Some(goal) = self.enemy_goal(team) else {
+            return 0.0;
+        };
+        let total = self.home.distance(goal).max(1.0);
+        (1.0 - self.position.distance(goal) / total).clamp(0.0, 1.0)
+    }
+
+    // 持っているチームの記録を更新する
+    pub fn record_progress(&mut self) {
+        let Some((_, team)) = self.carrier else {
+            return;
+        };
+        let progress = self.progress_of(team);
+        let best = self.best.entry(team).or_insert(0.0);
+        *best = best.max(progress);
+    }
+
+    pub fn best(&self, team: Team) -> f32 {
+        self.best.get(&team).copied().unwrap_or(0.0)
+    }
+
+    // 相手のゴールまで運んだチーム
+    pub fn delivered_by(&self) -> Option<Team> {
+        let (_, team) = self.carrier?;
+        let goal = self.enemy_goal(team)?;
+        (self.position.distance(goal) <= RAINMAKER_GOAL_RADIUS).then_some(team)
+    }
+
+    // 時間切れのときは相手のゴールへ最も近づけたチームの勝ち
+    pub fn leader(&self) -> Option<Team> {
+        let blue = self.best(Team::Blue);
+        let orange = self.best(Team::Orange);
+        if blue > orange {
+            Some(Team::Blue)
+        } else if orange > blue {
+            Some(Team::Orange)
+        } else {
+            None
+        }
+    }
+}
+
+// 右スティックをこれ以上倒したときだけ狙いを変える
+pub const GAMEPAD_AIM_DEADZONE: f32 = 0.3;
+// ゲームパッドで狙う位置までの距離
+pub const GAMEPAD_AIM_DISTANCE: f32 = 200.0;
+
+// ボットの強さ。反応の速さ・狙いのぶれ・見える距離・逃げ始める体力が変わる
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BotDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl BotDifficulty {
+    pub fn from_name(name: &str) -> Option<BotDifficulty> {
+        match name {
+            "easy" => Some(BotDifficulty::Easy),
+            "normal" => Some(BotDifficulty::Normal),
+            "hard" => Some(BotDifficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn reaction_seconds(self) -> f32 {
+        match self {
+            BotDifficulty::Easy => 0.6,
+            BotDifficulty::Normal => 0.35,
+            BotDifficulty::Hard => 0.15,
+        }
+    }
+
+    // 狙いのぶれ（度）
+    pub fn aim_error(self) -> f32 {
+        match self {
+            BotDifficulty::Easy => 20.0,
+            BotDifficulty::Normal => 10.0,
+            BotDifficulty::Hard => 3.0,
+        }
+    }
+
+    pub fn sight(self) -> f32 {
+        match self {
+            BotDifficulty::Easy => 320.0,
+            BotDifficulty::Normal => 420.0,
+            BotDifficulty::Hard => 520.0,
+        }
+    }
+
+    pub fn retreat_health(self) -> i32 {
+        match self {
+            BotDifficulty::Easy => 20,
+            BotDifficulty::Normal => 35,
+            BotDifficulty::Hard => 50,
+        }
+    }
+}
+
+// 協力モード。全員 blue で、マップの端から湧く敵の波を耐えながら
+// ボスが落とした金イクラをカゴに納品し、波ごとのノルマを達成する
+pub const COOP_WAVES: u32 = 3;
+// 協力モードで一緒に遊べる人数（ボットと分割画面の 2P を含む）
+pub const COOP_MAX_PLAYERS: usize = 4;
+pub const COOP_WAVE_SECONDS: f32 = 55.0;
+// 敵は orange として扱う。敵の攻撃の ID は ENEMY_ID
+pub const ENEMY_TEAM: Team = Team::Orange;
+pub const ENEMY_ID: Uuid = Uuid::nil();
+pub const MAX_ENEMIES: usize = 24;
+pub const MINION_SPAWN_SECONDS: f32 = 2.5;
+pub const BOSS_SPAWN_SECONDS: f32 = 12.0;
+pub const BOSS_EGGS: u32 = 3;
+pub const EGG_PICKUP_RADIUS: f32 = 24.0;
+pub const BASKET_RADIUS: f32 = 48.0;
+// ホストが敵の状態を送る間隔
+pub const COOP_SYNC_SECONDS: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnemyKind {
+    // 数が多く速い雑魚
+    Minion,
+    // 硬く遅いが、倒すと金イクラを落とす
+    Boss,
+}
+
+impl EnemyKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EnemyKind::Minion => "minion",
+            EnemyKind::Boss => "boss",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<EnemyKind> {
+        match name {
+            "minion" => Some(EnemyKind::Minion),
+            "boss" => Some(EnemyKind::Boss),
+            _ => None,
+        }
+    }
+
+    pub fn max_health(self) -> i32 {
+        match self {
+            EnemyKind::Minion => 80,
+            EnemyKind::Boss => 400,
+        }
+    }
+
+    pub fn speed(self) -> f32 {
+        match self {
+            EnemyKind::Minion => 110.0,
+            EnemyKind::Boss => 70.0,
+        }
+    }
+
+    pub fn damage(self) -> i32 {
+        match self {
+            EnemyKind::Minion => 30,
+            EnemyKind::Boss => 60,
+        }
+    }
+
+    pub fn size(self) -> f32 {
+        match self {
+            EnemyKind::Minion => 26.0,
+            EnemyKind::Boss => 40.0,
+        }
+    }
+}
+
+// ホストから届いた敵1体の状態
+#[derive(Clone, Debug)]
+pub struct EnemyState {
+    pub id: u32,
+    pub kind: EnemyKind,
+    pub position: Vec2,
+    pub health: i32,
+}
+
+#[derive(Resource, Default)]
+pub struct CoopWaves {
+    // 1 から数える
+    pub wave: u32,
+    pub delivered: u32,
+    pub wave_timer: Timer,
+    pub minion_timer: Timer,
+    pub boss_timer: Timer,
+    pub sync_timer: Timer,
+    pub next_id: u32,
+    pub basket: Vec2,
+    // 敵が湧くマップの端の床
+    pub spawn_edges: Vec<Vec2>,
+    pub failed: bool,
+    pub cleared: bool,
+    // ホスト以外が受け取った最新の敵と金イクラ
+    pub enemies: Vec<EnemyState>,
+    pub eggs: Vec<(u32, Vec2)>,
+}
+
+impl CoopWaves {
+    pub fn new(basket: Vec2, spawn_edges: Vec<Vec2>) -> Self {
+        Self {
+            wave: 1,
+            delivered: 0,
+            wave_timer: Timer::from_seconds(COOP_WAVE_SECONDS, TimerMode::Once),
+            minion_timer: Timer::from_seconds(MINION_SPAWN_SECONDS, TimerMode::Repeating),
+            boss_timer: Timer::from_seconds(BOSS_SPAWN_SECONDS, TimerMode::Repeating),
+            sync_timer: Timer::from_seconds(COOP_SYNC_SECONDS, TimerMode::Repeating),
+            next_id: 0,
+            basket,
+            spawn_edges,
+            failed: false,
+            cleared: false,
+            enemies: Vec::new(),
+            eggs: Vec::new(),
+        }
+    }
+
+    // 波ごとの金イクラのノルマ
+    pub fn quota(&self) -> u32 {
+        3 + (self.wave.max(1) - 1) * 2
+    }
+
+    pub fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn finished(&self) -> bool {
+        self.failed || self.cleared
+    }
+
+    // 波の終わりにノルマを確かめ、達成していれば次の波へ進む
+    pub fn end_wave(&mut self) {
+        if self.delivered < self.quota() {
+            self.failed = true;
+        } else if self.wave >= COOP_WAVES {
+            self.cleared = true;
+        } else {
+            self.wave += 1;
+            self.delivered = 0;
+            self.wave_timer.reset();
+            // 波が進むほど雑魚が早く湧く
+            let interval = MINION_SPAWN_SECONDS / self.wave as f32;
+            self.minion_timer = Timer::from_seconds(interval, TimerMode::Repeating);
+        }
+    }
+}
+
+// マップエディタ。F1 キーか --editor で切り替える
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EditorState {
+    #[default]
+    Off,
+    On,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EditorBrush {
+    Tile(TileKind),
+    Spawn,
+    Objective(ObjectiveKind),
+    // クリックした順にヤグラの通り道へ点を足す
+    TowerPath,
+}
+
+pub const EDITOR_HISTORY_LIMIT: usize = 100;
+
+#[derive(Resource)]
+pub struct MapEditor {
+    pub brush: EditorBrush,
+    // リスポーン地点とゴールを置くチーム
+    pub team: Team,
+    pub undo: Vec<MapDef>,
+    pub redo: Vec<MapDef>,
+    // 目印をドラッグで置くときの始点
+    pub drag_start: Option<(i32, i32)>,
+    // マップを書き換えたのでタイルを作り直す必要がある
+    pub dirty: bool,
+    // 今のドラッグで元に戻す用のマップを既に取ってある
+    pub stroke_recorded: bool,
+}
+
+impl Default for MapEditor {
+    fn default() -> Self {
+        Self {
+            brush: EditorBrush::Tile(TileKind::Wall),
+            team: Team::Blue,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            drag_start: None,
+            dirty: false,
+            stroke_recorded: false,
+        }
+    }
+}
+
+impl MapEditor {
+    // 編集の前に今のマップを取っておく
+    pub fn snapshot(&mut self, map: &MapDef) {
+        self.undo.push(map.clone());
+        if self.undo.len() > EDITOR_HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    // ドラッグ1回分の編集をまとめて戻せるよう、そのドラッグで最初に書き換えるときだけ取っておく
+    pub fn snapshot_stroke(&mut self, map: &MapDef) {
+        if !self.stroke_recorded {
+            self.snapshot(map);
+            self.stroke_recorded = true;
+        }
+    }
+}
+
+pub const SPAWN_AREA_RADIUS: f32 = 96.0;
+// 壁との当たり判定に使うプレイヤーの半径（正方形の半分の幅）
+pub const PLAYER_COLLISION_HALF_SIZE: f32 = 14.0;
+
+pub const DEFAULT_MAP_PATH: &str = "assets/maps/ink_plaza.ron";
+
+// ステージの情報。読み込んだマップとチームごとのリスポーン地点を持つ
+#[derive(Resource, Default)]
+pub struct Arena {
+    pub map: MapDef,
+    pub spawn_points: HashMap<Team, Vec2>,
+}
+
+impl Arena {
+    pub fn set_map(&mut self, map: MapDef) {
+        self.map = map;
+        self.refresh_spawn_points();
+    }
+
+    pub fn refresh_spawn_points(&mut self) {
+        self.spawn_points = self
+            .map
+            .spawns
+            .iter()
+            .filter_map(|(name, tile)| Some((Team::from_name(name)?, tile_to_world(*tile))))
+            .collect();
+    }
+
+    pub fn spawn_point(&self, team: Team) -> Vec2 {
+        self.spawn_points.get(&team).copied().unwrap_or(Vec2::ZERO)
+    }
+
+    // 相手チームのリスポーン地点周辺は塗れない
+    pub fn is_protected_from(&self, tile: (i32, i32), team: Team) -> bool {
+        let position = tile_to_world(tile);
+        self.spawn_points
+            .iter()
+            .any(|(owner, spawn)| *owner != team && spawn.distance(position) <= SPAWN_AREA_RADIUS)
+    }
+
+    // プレイヤーが入れないタイル（壁・水場・マップの外）
+    pub fn blocks_player(&self, tile: (i32, i32)) -> bool {
+        matches!(self.map.tile(tile), TileKind::Wall | TileKind::Water)
+    }
+
+    pub fn is_wall(&self, tile: (i32, i32)) -> bool {
+        self.map.tile(tile) == TileKind::Wall
+    }
+
+    pub fn blocks_projectile(&self, position: Vec2) -> bool {
+        self.map.tile(world_to_tile(position)) == TileKind::Wall
+    }
+
+    // 中心 center・半幅 half_size の正方形が入れないタイルに重なっているか。
+    // climbable が true を返す壁（イカで登れる自チームの壁）は通れる
+    pub fn collides(
+        &self,
+        center: Vec2,
+        half_size: f32,
+        climbable: &impl Fn((i32, i32)) -> bool,
+    ) -> bool {
+        let (min_x, min_y) = world_to_tile(center - Vec2::splat(half_size));
+        let (max_x, max_y) = world_to_tile(center + Vec2::splat(half_size));
+
+        (min_x..=max_x)
+            .any(|x| (min_y..=max_y).any(|y| self.blocks_player((x, y)) && !climbable((x, y))))
+    }
+
+    // 入れないタイルと重なっている面積。壁の中から抜け出す向きかどうかの判定に使う
+    fn overlap(
+        &self,
+        center: Vec2,
+        half_size: f32,
+        climbable: &impl Fn((i32, i32)) -> bool,
+    ) -> f32 {
+        let (min_x, min_y) = world_to_tile(center - Vec2::splat(half_size));
+        let (max_x, max_y) = world_to_tile(center + Vec2::splat(half_size));
+
+        let mut area = 0.0;
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if !self.blocks_player((x, y)) || climbable((x, y)) {
+                    continue;
+                }
+                let tile = tile_to_world((x, y));
+                let size = (Vec2::splat(TILE_SIZE / 2.0 + half_size) - (center - tile).abs())
+                    .clamp(
+                        Vec2::ZERO,
+                        Vec2::splat(2.0 * half_size.min(TILE_SIZE / 2.0)),
+                    );
+                area += size.x * size.y;
+            }
+        }
+        area
+    }
+
+    // 軸ごとに動かし、ぶつかった軸だけ止めることで壁に沿って滑らせる。
+    // 壁の上でイカをやめた場合など既に重なっているときは、重なりが減る向きにだけ動ける
+    pub fn slide(
+        &self,
+        from: Vec2,
+        delta: Vec2,
+        half_size: f32,
+        climbable: impl Fn((i32, i32)) -> bool,
+    ) -> Vec2 {
+        let mut position = from;
+        let mut overlap = self.overlap(from, half_size, &climbable);
+        // 完全に埋まっているとどの向きにも重なりが減らないので、近くの空いているタイルへ押し出す
+        let side = 2.0 * half_size.min(TILE_SIZE / 2.0);
+        if overlap >= side * side {
+            return self
+                .nearest_free(from, half_size, &climbable)
+                .unwrap_or(from);
+        }
+
+        for step in [Vec2::new(delta.x, 0.0), Vec2::new(0.0, delta.y)] {
+            let moved = position + step;
+            if !self.collides(moved, half_size, &climbable) {
+                position = moved;
+                overlap = 0.0;
+                continue;
+            }
+
+            let moved_overlap = self.overlap(moved, half_size, &climbable);
+            if moved_overlap < overlap {
+                position = moved;
+                overlap = moved_overlap;
+            }
+        }
+        position
+    }
+
+    // 重ならずにいられる一番近いタイルの中心
+    fn nearest_free(
+        &self,
+        center: Vec2,
+        half_size: f32,
+        climbable: &impl Fn((i32, i32)) -> bool,
+    ) -> Option<Vec2> {
+        let (x, y) = world_to_tile(center);
+        (1..=4).find_map(|radius| {
+            (x - radius..=x + radius)
+                .flat_map(|tx| (y - radius..=y + radius).map(move |ty| tile_to_world((tx, ty))))
+                .filter(|position| !self.collides(*position, half_size, climbable))
+                .min_by(|a, b| a.distance(center).total_cmp(&b.distance(center)))
+        })
+    }
+
+    // 直線上で最初に壁に当たるまでの距離
+    pub fn first_wall_on_line(&self, origin: Vec2, direction: Vec2, length: f32) -> Option<f32> {
+        let mut traveled = 0.0;
+        while traveled <= length {
+            if self.blocks_projectile(origin + direction * traveled) {
+                return Some(traveled);
+            }
+            traveled += TILE_SIZE / 4.0;
+        }
+        None
+    }
+}
+
+// タイル座標から地面エンティティを引くための索引
+#[derive(Resource, Default)]
+pub struct GroundTiles(pub HashMap<(i32, i32), Entity>);
+
+#[derive(Resource, Default)]
+pub struct WallTiles(pub HashMap<(i32, i32), Entity>);
+
+// ゲームの進行は 1 秒に 64 回の固定ティックで進める。
+// 1/64 秒は f32 でちょうど表せるので、どの端末でも同じ入力から同じ結果になる
+pub const SIMULATION_HZ: f64 = 64.0;
+// 位置はこの細かさ（1/64 ピクセル）に丸め、浮動小数の誤差をティックをまたいで持ち越さない
+pub const POSITION_QUANTUM: f32 = 64.0;
+
+pub fn quantize_position(position: Vec2) -> Vec2 {
+    (position * POSITION_QUANTUM).round() / POSITION_QUANTUM
+}
+
+// 試合開始からのティック数。送るメッセージに付け、受け取った側は順番の入れ替わった位置を捨てるのに使う
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SimulationTick(pub u64);
+
+impl SimulationTick {
+    // 最後に受け取った last より前のティックか。1 秒以上前なら、送った側が試合開始で数え直したとみなす
+    pub fn is_stale(tick: u64, last: u64) -> bool {
+        tick < last && last - tick < SIMULATION_HZ as u64
+    }
+}
+
+// シミュレーションで使う乱数（ばらつき・ボットの判断・協力モードの湧き位置）。
+// 試合開始でシードから作り直し、FixedUpdate の中だけで進めるので、同じシードと入力なら同じ結果になる
+#[derive(Resource)]
+pub struct SimulationRng {
+    pub seed: u64,
+    rng: fastrand::Rng,
+}
+
+impl Default for SimulationRng {
+    fn default() -> Self {
+        Self::with_seed(fastrand::u64(..))
+    }
+}
+
+impl SimulationRng {
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: fastrand::Rng::with_seed(seed),
+        }
+    }
+
+    pub fn reseed(&mut self) {
+        self.rng = fastrand::Rng::with_seed(self.seed);
+    }
+
+    pub fn f32(&mut self) -> f32 {
+        self.rng.f32()
+    }
+
+    pub fn usize(&mut self, range: std::ops::RangeTo<usize>) -> usize {
+        self.rng.usize(range)
+    }
+}
+
 #[derive(Resource)]
 pub struct NetworkClient {
     pub sender: Option<Sender<String>>,
     pub receiver: Option<Arc<Mutex<Receiver<String>>>>,
     pub player_id: Uuid,
     pub connected: bool,
+    // サーバーに繋がずにローカルだけで遊ぶ（--offline かロビーで O キー）
+    pub offline: bool,
+    // 試合の進行とルールの状態を決めるクライアント（--host）。
+    // サーバーはホストの状態だけを他のクライアントへ中継し、他のクライアントはそれに合わせる
+    pub host: bool,
+    // 分割画面の 2P のように、別のクライアントとしてサーバーに繋いだプレイヤーの送信口
+    pub extra_senders: HashMap<Uuid, Sender<String>>,
+}
+
+impl NetworkClient {
+    // そのプレイヤーの行動を送る接続。別に繋いでいなければ自分の接続を使う
+    pub fn sender_for(&self, player: Uuid) -> Option<&Sender<String>> {
+        self.extra_senders.get(&player).or(self.sender.as_ref())
+    }
+
+    // オフラインなら自分しかいないので自分がホスト
+    pub fn is_host(&self) -> bool {
+        self.host || self.offline
+    }
 }
 
 impl Default for NetworkClient {
@@ -40,6 +1426,9 @@ impl Default for NetworkClient {
             receiver: None,
             player_id: Uuid::new_v4(),
             connected: false,
+            offline: false,
+            host: false,
+            extra_senders: HashMap::new(),
         }
     }
 }
@@ -51,4 +1440,116 @@ pub enum GameMessage {
     PlayerShoot { player_id: Uuid, direction: Vec2 },
     TilePainted { position: (i32, i32), color: Color },
     GameState { players: Vec<PlayerState>, painted_tiles: HashMap<(i32, i32), Color> },
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_ticks_are_stale_unless_the_sender_started_over() {
+        assert!(SimulationTick::is_stale(99, 100));
+        assert!(!SimulationTick::is_stale(100, 100));
+        assert!(!SimulationTick::is_stale(101, 100));
+        // 試合開始で 0 に戻った送り主の位置は受け取る
+        assert!(!SimulationTick::is_stale(3, 5000));
+    }
+
+    // 原点のタイルだけが壁のステージ
+    fn arena_with_wall_at_origin() -> Arena {
+        let mut map = MapDef::default();
+        map.set_tile((0, 0), TileKind::Wall);
+        let mut arena = Arena::default();
+        arena.set_map(map);
+        arena
+    }
+
+    // 長さ 400 の直線で、blue 側（相手の陣地）の 300 にチェックポイントがあるヤグラ
+    fn tower_with_checkpoint() -> TowerControl {
+        let mut tower = TowerControl::new(vec![Vec2::ZERO, Vec2::new(400.0, 0.0)]);
+        tower.set_checkpoints(vec![300.0]);
+        tower
+    }
+
+    #[test]
+    fn tower_waits_at_checkpoint_only_for_the_team_that_reached_it() {
+        let mut tower = tower_with_checkpoint();
+        tower.push(Team::Blue, 3.0);
+        assert_eq!(tower.progress, 300.0);
+        assert_eq!(
+            tower.checkpoint_wait,
+            Some((Team::Blue, TOWER_CHECKPOINT_SECONDS))
+        );
+
+        tower.push(Team::Blue, 1.0);
+        assert_eq!(tower.progress, 300.0);
+
+        // 相手が乗り換えればすぐに押し戻せ、待ち時間は消える
+        tower.push(Team::Orange, 1.0);
+        assert_eq!(tower.progress, 300.0 - TOWER_SPEED);
+        assert_eq!(tower.checkpoint_wait, None);
+
+        // 通過済みのチェックポイントでは二度止まらない
+        tower.push(Team::Blue, 2.0);
+        assert_eq!(tower.progress, 300.0 + TOWER_SPEED);
+        assert_eq!(tower.cleared, vec![true]);
+    }
+
+    #[test]
+    fn tower_moves_on_after_checkpoint_wait() {
+        let mut tower = tower_with_checkpoint();
+        tower.push(Team::Blue, 3.0);
+        tower.push(Team::Blue, TOWER_CHECKPOINT_SECONDS);
+        assert_eq!(tower.checkpoint_wait, None);
+        tower.push(Team::Blue, 1.0);
+        assert_eq!(tower.progress, 300.0 + TOWER_SPEED);
+    }
+
+    #[test]
+    fn slide_stops_at_walls_and_slides_along_them() {
+        let arena = arena_with_wall_at_origin();
+        let from = Vec2::new(-TILE_SIZE, 0.0);
+
+        assert_eq!(
+            arena.slide(from, Vec2::new(8.0, 0.0), 12.0, |_| false),
+            from
+        );
+        assert_eq!(
+            arena.slide(from, Vec2::new(8.0, 5.0), 12.0, |_| false),
+            from + Vec2::new(0.0, 5.0)
+        );
+        assert_eq!(
+            arena.slide(from, Vec2::new(8.0, 0.0), 12.0, |_| true),
+            from + Vec2::new(8.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn slide_inside_a_wall_only_moves_out_of_it() {
+        let arena = arena_with_wall_at_origin();
+        let from = Vec2::new(-20.0, 0.0);
+
+        // 奥へも壁に沿っても進めず、抜け出す向きには動ける
+        assert_eq!(
+            arena.slide(from, Vec2::new(4.0, 0.0), 12.0, |_| false),
+            from
+        );
+        assert_eq!(
+            arena.slide(from, Vec2::new(0.0, 4.0), 12.0, |_| false),
+            from
+        );
+        assert_eq!(
+            arena.slide(from, Vec2::new(-4.0, 4.0), 12.0, |_| false),
+            from + Vec2::new(-4.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn slide_pushes_out_of_a_wall_it_is_buried_in() {
+        let arena = arena_with_wall_at_origin();
+        assert_eq!(
+            arena.slide(Vec2::new(-4.0, 0.0), Vec2::new(4.0, 0.0), 12.0, |_| false),
+            Vec2::new(-TILE_SIZE, 0.0)
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::components::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+pub fn advance_simulation_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+pub fn reset_simulation_tick(mut tick: ResMut<SimulationTick>, mut rng: ResMut<SimulationRng>) {
+    *tick = SimulationTick::default();
+    rng.reseed();
+}
+
+// --seed <n> で乱数のシードを決める（省略すると起動ごとに変わる）
+pub fn select_seed_from_args(mut rng: ResMut<SimulationRng>) {
+    let args = std::env::args().collect::<Vec<_>>();
+    let Some(seed) = args
+        .iter()
+        .position(|arg| arg == "--seed")
+        .and_then(|index| args.get(index + 1))
+        .and_then(|seed| seed.parse::<u64>().ok())
+    else {
+        return;
+    };
+    info!("🎲 Simulation seed {}", seed);
+    *rng = SimulationRng::with_seed(seed);
+}
+
+// Transform が補間した位置でもシミュレーション上の位置でもなければ、
+// ティックの外（試合開始など）で動かされたので、その位置から始め直す
+fn moved_outside_tick(position: Vec2, simulated: &SimulatedPosition) -> bool {
+    position != simulated.rendered && position != simulated.current
+}
+
+// ティックの始めに、描画用に補間した位置をシミュレーション上の位置へ戻す
+pub fn restore_simulated_positions(mut query: Query<(&mut Transform, &mut SimulatedPosition)>) {
+    for (mut transform, mut simulated) in query.iter_mut() {
+        let position = transform.translation.truncate();
+        if moved_outside_tick(position, &simulated) {
+            *simulated = SimulatedPosition::new(position);
+        } else if position != simulated.current {
+            transform.translation.x = simulated.current.x;
+            transform.translation.y = simulated.current.y;
+        }
+    }
+}
+
+// ティックの終わりに位置を記録する。止まっている物は書き換えず、変更検知に引っかからないようにする
+pub fn record_simulated_positions(mut query: Query<(&Transform, &mut SimulatedPosition)>) {
+    for (transform, mut simulated) in query.iter_mut() {
+        let position = transform.translation.truncate();
+        if simulated.previous != simulated.current || simulated.current != position {
+            simulated.previous = simulated.current;
+            simulated.current = position;
+        }
+    }
+}
+
+// 次のティックまでの経過に合わせて、直前の2ティックの位置の間に描画する
+pub fn interpolate_simulated_positions(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut SimulatedPosition)>,
+) {
+    let alpha = fixed_time.overstep_percentage();
+    for (mut transform, mut simulated) in query.iter_mut() {
+        if moved_outside_tick(transform.translation.truncate(), &simulated) {
+            *simulated = SimulatedPosition::new(transform.translation.truncate());
+            continue;
+        }
+
+        let position = simulated.previous.lerp(simulated.current, alpha);
+        if position != simulated.rendered || position != transform.translation.truncate() {
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+            simulated.rendered = position;
+        }
+    }
+}
+
+// 押した・離した瞬間の操作はそのティックで使い切る
+pub fn clear_input_edges(mut query: Query<&mut PlayerInput>) {
+    for mut input in query.iter_mut() {
+        input.clear_edges();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::*;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    // 同じシードから作った操作で ticks 回動かし、毎ティックの位置を返す
+    fn simulate(seed: u64, ticks: usize) -> Vec<Vec2> {
+        let mut world = World::new();
+        let arena = Arena::default();
+        let spawn = arena.spawn_point(Team::Blue);
+        world.insert_resource(arena);
+        world.insert_resource(GameState::default());
+        world.insert_resource(TerrainModifiers::default());
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(SimulationRng::with_seed(seed));
+        let player = world
+            .spawn((
+                Transform::from_translation(spawn.extend(0.0)),
+                Velocity(Vec2::ZERO),
+                Player {
+                    id: Uuid::nil(),
+                    team: Team::Blue,
+                },
+                PlayerInput::default(),
+                SquidForm::default(),
+            ))
+            .id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((squid_form_toggle, player_movement).chain());
+
+        (0..ticks)
+            .map(|_| {
+                let mut rng = world.resource_mut::<SimulationRng>();
+                let input = PlayerInput {
+                    movement: Vec2::from_angle(rng.f32() * std::f32::consts::TAU),
+                    squid: rng.f32() < 0.5,
+                    ..default()
+                };
+                *world.get_mut::<PlayerInput>(player).unwrap() = input;
+                world
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_secs_f64(1.0 / SIMULATION_HZ));
+                schedule.run(&mut world);
+                world
+                    .get::<Transform>(player)
+                    .unwrap()
+                    .translation
+                    .truncate()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_same_positions() {
+        let first = simulate(7, 256);
+        assert_eq!(first, simulate(7, 256));
+        assert_ne!(first, simulate(8, 256));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::components::*;
+use crate::events::*;
+use bevy::prelude::*;
+
+// 新しく塗ったタイルの分だけ自分のスペシャルゲージを溜める
+pub fn charge_special_gauge(
+    mut inked_events: EventReader<TurfInkedEvent>,
+    mut player_query: Query<(&Player, &mut SpecialGauge), Without<InkArmor>>,
+) {
+    for event in inked_events.read() {
+        for (player, mut gauge) in player_query.iter_mut() {
+            if player.id == event.player {
+                gauge.add(event.tiles);
+            }
+        }
+    }
+}
+
+// ゲージが満タンならスペシャルを発動する（人は E キー）
+pub fn activate_special(
+    mut player_query: Query<(&Player, &PlayerInput, &mut SpecialGauge), Without<Splatted>>,
+    mut special_events: EventWriter<SpecialActivatedEvent>,
+) {
+    for (player, input, mut gauge) in player_query.iter_mut() {
+        if !input.special || !gauge.is_full() {
+            continue;
+        }
+
+        gauge.points = 0.0;
+        special_events.send(SpecialActivatedEvent {
+            player: player.id,
+            team: player.team,
+        });
+    }
+}
+
+// 発動したプレイヤー（自分・他プレイヤーとも）にインクアーマーを付ける
+pub fn resolve_specials(
+    mut commands: Commands,
+    mut special_events: EventReader<SpecialActivatedEvent>,
+    player_query: Query<(Entity, &Player)>,
+    remote_query: Query<(Entity, &RemotePlayer)>,
+) {
+    for event in special_events.read() {
+        let local = player_query
+            .iter()
+            .filter(|(_, player)| player.id == event.player)
+            .map(|(entity, _)| entity);
+        let remote = remote_query
+            .iter()
+            .filter(|(_, remote)| remote.id == event.player)
+            .map(|(entity, _)| entity);
+
+        for entity in local.chain(remote) {
+            info!("🛡️ Ink armor activated");
+            commands
+                .entity(entity)
+                .insert(InkArmor::default())
+                .with_children(|parent| {
+                    parent.spawn((
+                        ArmorShell,
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: event.team.color().with_a(0.35),
+                                custom_size: Some(Vec2::splat(42.0)),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(0.0, 0.0, -0.1),
+                            ..default()
+                        },
+                    ));
+                });
+        }
+    }
+}
+
+pub fn tick_ink_armor(
+    mut commands: Commands,
+    mut armor_query: Query<(Entity, &mut InkArmor, &Children)>,
+    shell_query: Query<Entity, With<ArmorShell>>,
+    time: Res<Time>,
+) {
+    for (entity, mut armor, children) in armor_query.iter_mut() {
+        if !armor.0.tick(time.delta()).finished() {
+            continue;
+        }
+
+        commands.entity(entity).remove::<InkArmor>();
+        for child in children.iter() {
+            if shell_query.contains(*child) {
+                commands.entity(*child).despawn();
+            }
+        }
+    }
+}
+
+// 新しい試合ではゲージを空にしてアーマーも外す
+pub fn reset_special_gauges(
+    mut commands: Commands,
+    mut gauge_query: Query<&mut SpecialGauge>,
+    armor_query: Query<Entity, With<InkArmor>>,
+    shell_query: Query<Entity, With<ArmorShell>>,
+) {
+    for mut gauge in gauge_query.iter_mut() {
+        gauge.points = 0.0;
+    }
+
+    // ゲージを持たない他プレイヤーのアーマーも外す
+    for entity in armor_query.iter() {
+        commands.entity(entity).remove::<InkArmor>();
+    }
+
+    for shell in shell_query.iter() {
+        commands.entity(shell).despawn();
+    }
+}
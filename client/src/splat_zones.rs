@@ -0,0 +1,147 @@
+use crate::arena::OBJECTIVE_COLOR;
+use crate::components::*;
+use crate::map::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+// 試合開始時にマップのガチエリアからエリアを作る
+pub fn reset_splat_zones(arena: Res<Arena>, mut splat_zones: ResMut<SplatZones>) {
+    let zones = arena
+        .map
+        .objectives
+        .iter()
+        .enumerate()
+        .filter(|(_, objective)| objective.kind == ObjectiveKind::SplatZone)
+        .map(|(index, objective)| Zone {
+            objective: index,
+            tiles: arena
+                .map
+                .iter_tiles()
+                .filter(|(tile, kind)| *kind == TileKind::Paintable && objective.contains(*tile))
+                .map(|(tile, _)| tile)
+                .collect(),
+            owner: None,
+        })
+        .collect::<Vec<_>>();
+
+    if zones.is_empty() {
+        warn!("Map {} has no splat zones", arena.map.name);
+    }
+    *splat_zones = SplatZones::new(zones);
+}
+
+// エリアの塗り具合で持ち主を決め、確保中のチームのカウントを進める
+pub fn update_splat_zones(
+    game_state: Res<GameState>,
+    mut splat_zones: ResMut<SplatZones>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+    time: Res<Time>,
+) {
+    for zone in splat_zones.zones.iter_mut() {
+        if zone.tiles.is_empty() {
+            continue;
+        }
+
+        for team in [Team::Blue, Team::Orange] {
+            let inked = zone
+                .tiles
+                .iter()
+                .filter(|tile| {
+                    game_state
+                        .painted_tiles
+                        .get(tile)
+                        .and_then(|color| Team::from_color(*color))
+                        == Some(team)
+                })
+                .count();
+            let percent = inked as f32 / zone.tiles.len() as f32 * 100.0;
+
+            if percent >= ZONE_CONTROL_PERCENT && zone.owner != Some(team) {
+                info!("🟨 {} team captured a splat zone", team.name());
+                zone.owner = Some(team);
+            }
+        }
+    }
+
+    let controller = splat_zones.full_control();
+    splat_zones.set_controller(controller);
+    splat_zones.tick(time.delta_seconds());
+
+    // カウントが 0 になったらノックアウト
+    if let Some(team) = splat_zones.controller {
+        if splat_zones.count(team) <= 0.0 {
+            info!("💥 Knockout by {} team!", team.name());
+            next_phase.set(MatchPhase::Finished);
+        }
+    }
+}
+
+pub fn tally_splat_zones(splat_zones: Res<SplatZones>, mut result: ResMut<MatchResult>) {
+    *result = MatchResult {
+        winner: splat_zones.leader(),
+        scores: [Team::Blue, Team::Orange]
+            .into_iter()
+            .map(|team| (team, format!("{:.0} count", splat_zones.count(team).ceil())))
+            .collect(),
+        ..default()
+    };
+
+    info!(
+        "📋 Results - Blue: {:.0}, Orange: {:.0}",
+        splat_zones.count(Team::Blue),
+        splat_zones.count(Team::Orange)
+    );
+}
+
+// エリアの目印を持ち主の色で表示する
+pub fn update_zone_markers(
+    splat_zones: Res<SplatZones>,
+    mut marker_query: Query<(&ObjectiveMarker, &mut Sprite)>,
+) {
+    if !splat_zones.is_changed() {
+        return;
+    }
+
+    for (marker, mut sprite) in marker_query.iter_mut() {
+        let Some(zone) = splat_zones
+            .zones
+            .iter()
+            .find(|zone| zone.objective == marker.0)
+        else {
+            continue;
+        };
+
+        sprite.color = zone
+            .owner
+            .map_or(OBJECTIVE_COLOR, |team| team.color().with_a(0.35));
+    }
+}
+
+pub fn update_splat_zones_text(
+    splat_zones: Res<SplatZones>,
+    mut text_query: Query<&mut Text, With<ModeScoreText>>,
+) {
+    let label = [Team::Blue, Team::Orange]
+        .into_iter()
+        .map(|team| {
+            let penalty = splat_zones.penalty(team);
+            let mut label = format!(
+                "{} {:.0}",
+                team.name().to_uppercase(),
+                splat_zones.count(team).ceil()
+            );
+            if penalty > 0.0 {
+                label.push_str(&format!(" (+{:.0})", penalty.ceil()));
+            }
+            if splat_zones.controller == Some(team) {
+                label.push_str(" (in control)");
+            }
+            label
+        })
+        .collect::<Vec<_>>()
+        .join("   |   ");
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = label.clone();
+    }
+}
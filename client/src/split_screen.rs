@@ -0,0 +1,183 @@
+use crate::components::*;
+use crate::resources::*;
+use crate::systems::*;
+use crate::udp_network::*;
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use bevy::render::view::RenderLayers;
+use uuid::Uuid;
+
+// HUD 用カメラだけが見るレイヤー。ここには何も置かないので HUD だけが描かれる
+const HUD_LAYER: u8 = 1;
+
+// --split-screen でゲームパッドで操作する 2P を加え、画面を左右に分ける。
+// --p2-team blue / orange で 2P のチームを選ぶ（協力モードでは味方、それ以外では相手チームが既定）。
+// オンラインなら 2P も別のクライアントとしてサーバーに参加する
+pub fn spawn_split_screen_from_args(
+    mut commands: Commands,
+    mut network_client: ResMut<NetworkClient>,
+    game_mode: Res<GameMode>,
+    arena: Res<Arena>,
+    weapon_registry: Res<WeaponRegistry>,
+    sub_weapon_registry: Res<SubWeaponRegistry>,
+    camera_query: Query<Entity, With<PlayerCamera>>,
+) {
+    let args = std::env::args().collect::<Vec<_>>();
+    if !args.iter().any(|arg| arg == "--split-screen") {
+        return;
+    }
+
+    let default_team = if *game_mode == GameMode::CoopWaves {
+        Team::Blue
+    } else {
+        Team::Orange
+    };
+    let team = args
+        .iter()
+        .position(|arg| arg == "--p2-team")
+        .and_then(|index| args.get(index + 1))
+        .map(|name| {
+            Team::from_name(name).unwrap_or_else(|| {
+                warn!("Unknown team: {}", name);
+                default_team
+            })
+        })
+        .unwrap_or(default_team);
+
+    let player_id = Uuid::new_v4();
+    let player = spawn_player(
+        &mut commands,
+        player_id,
+        team,
+        arena.spawn_point(team),
+        DEFAULT_WEAPON,
+        &weapon_registry,
+        &sub_weapon_registry,
+    );
+    commands.entity(player).insert(GamepadControl);
+
+    // プレイヤーのカメラはビューポートに合わせて HUD がずれるので、HUD は専用のカメラで画面全体に描く
+    for camera in camera_query.iter() {
+        commands
+            .entity(camera)
+            .insert(UiCameraConfig { show_ui: false });
+    }
+    // 後に描くカメラが画面をクリアすると 1P の描いた分まで消えるので、2P のカメラはクリアしない
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: 1,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::None,
+            },
+            ..default()
+        },
+        PlayerCamera { player },
+        UiCameraConfig { show_ui: false },
+    ));
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: 2,
+                ..default()
+            },
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::None,
+            },
+            ..default()
+        },
+        HudCamera,
+        RenderLayers::layer(HUD_LAYER),
+    ));
+
+    commands.spawn((
+        SecondPlayerText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: team.color(),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(16.0),
+            bottom: Val::Px(52.0),
+            ..default()
+        }),
+    ));
+
+    if !network_client.offline {
+        connect_extra_udp(&mut network_client, player_id, team, *game_mode);
+    }
+
+    info!(
+        "🎮 Split screen: 2P joins {} team with a gamepad",
+        team.name()
+    );
+}
+
+// プレイヤーのカメラが複数あれば、ウィンドウを左から順に等分する
+pub fn layout_split_screen(
+    windows: Query<&Window>,
+    mut camera_query: Query<&mut Camera, With<PlayerCamera>>,
+) {
+    let window = windows.single();
+    let (width, height) = (window.physical_width(), window.physical_height());
+    let count = camera_query.iter().count() as u32;
+    if count < 2 || width == 0 || height == 0 {
+        return;
+    }
+
+    let mut cameras = camera_query.iter_mut().collect::<Vec<_>>();
+    cameras.sort_by_key(|camera| camera.order);
+
+    let column = width / count;
+    for (index, camera) in cameras.iter_mut().enumerate() {
+        let position = UVec2::new(column * index as u32, 0);
+        let size = UVec2::new(column, height);
+        let unchanged = camera.viewport.as_ref().is_some_and(|viewport| {
+            viewport.physical_position == position && viewport.physical_size == size
+        });
+        if !unchanged {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: size,
+                ..default()
+            });
+        }
+    }
+}
+
+pub fn update_second_player_text(
+    player_query: Query<
+        (&Health, &InkTank, &SpecialGauge, Option<&Splatted>),
+        With<GamepadControl>,
+    >,
+    mut text_query: Query<&mut Text, With<SecondPlayerText>>,
+) {
+    let Some((health, ink_tank, special_gauge, splatted)) = player_query.iter().next() else {
+        return;
+    };
+    let label = if let Some(splatted) = splatted {
+        format!(
+            "2P  SPLATTED - respawn in {}",
+            splatted.respawn.remaining_secs().ceil() as u32
+        )
+    } else {
+        format!(
+            "2P  HP {}   INK {}%   SP {}%",
+            health.0.max(0),
+            (ink_tank.fraction() * 100.0).round() as u32,
+            (special_gauge.fraction() * 100.0).round() as u32
+        )
+    };
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = label.clone();
+    }
+}
@@ -1,123 +1,636 @@
 use crate::components::*;
+use crate::events::*;
+use crate::paint::*;
 use crate::resources::*;
 use bevy::prelude::*;
+use uuid::Uuid;
 
-pub fn player_movement(
+// プレイヤー（人・ボットとも）を生成する
+pub fn spawn_player(
+    commands: &mut Commands,
+    id: Uuid,
+    team: Team,
+    position: Vec2,
+    weapon: &str,
+    weapon_registry: &WeaponRegistry,
+    sub_weapon_registry: &SubWeaponRegistry,
+) -> Entity {
+    commands
+        .spawn((
+            Player { id, team },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: team.color(),
+                    custom_size: Some(Vec2::new(30.0, 30.0)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(position.x, position.y, 1.0),
+                ..default()
+            },
+            Velocity(Vec2::ZERO),
+            Health(100),
+            InkDamage::default(),
+            HealthRegen::default(),
+            Weapon {
+                id: weapon.to_string(),
+                stats: weapon_registry.get(weapon),
+            },
+            InkTank::default(),
+            SubWeapon {
+                id: DEFAULT_SUB_WEAPON.to_string(),
+                stats: sub_weapon_registry.get(DEFAULT_SUB_WEAPON),
+            },
+            SpecialGauge::default(),
+            (
+                FireCooldown::default(),
+                ChargeState::default(),
+                RollState::default(),
+                SquidForm::default(),
+                PlayerInput::default(),
+                SimulatedPosition::new(position),
+            ),
+        ))
+        .id()
+}
+
+// キーボードとマウスの操作を PlayerInput に書き込む。ボットの操作は think_bots が書き込む
+#[allow(clippy::type_complexity)]
+pub fn read_keyboard_input(
     keyboard_input: Res<Input<KeyCode>>,
-    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
-    time: Res<Time>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform, &PlayerCamera)>,
+    mut player_query: Query<(Entity, &mut PlayerInput), (Without<Bot>, Without<GamepadControl>)>,
 ) {
-    for (mut transform, mut velocity) in player_query.iter_mut() {
-        let mut direction = Vec2::ZERO;
+    let mut movement = Vec2::ZERO;
+    if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
+        movement.y += 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down) {
+        movement.y -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left) {
+        movement.x -= 1.0;
+    }
+    if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
+        movement.x += 1.0;
+    }
 
-        if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
-            direction.y += 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down) {
-            direction.y -= 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left) {
-            direction.x -= 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
-            direction.x += 1.0;
-        }
+    let window = windows.single();
+
+    for (entity, mut input) in player_query.iter_mut() {
+        // 分割画面ではそのプレイヤーのカメラで狙う位置を求める
+        let aim = camera_query
+            .iter()
+            .find(|(_, _, player_camera)| player_camera.player == entity)
+            .zip(window.cursor_position())
+            .and_then(|((camera, camera_transform, _), cursor)| {
+                cursor_to_world(camera, camera_transform, cursor)
+            });
 
-        if direction != Vec2::ZERO {
-            direction = direction.normalize();
-            velocity.0 = direction * 200.0;
+        input.latch(PlayerInput {
+            movement: movement.normalize_or_zero(),
+            aim,
+            fire: mouse_button_input.pressed(MouseButton::Left),
+            fire_released: mouse_button_input.just_released(MouseButton::Left),
+            squid: keyboard_input.pressed(KeyCode::ShiftLeft)
+                || keyboard_input.pressed(KeyCode::ShiftRight),
+            sub_weapon: mouse_button_input.just_pressed(MouseButton::Right),
+            special: keyboard_input.just_pressed(KeyCode::E),
+            switch_weapon: keyboard_input.just_pressed(KeyCode::Q),
+        });
+    }
+}
+
+// ゲームパッドの操作を PlayerInput に書き込む。
+// 左スティックで移動、右スティックで狙い、RT で撃つ、LT でイカ、RB でサブ、Y でスペシャル、X で武器の切り替え
+pub fn read_gamepad_input(
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut player_query: Query<(&Transform, &mut PlayerInput), With<GamepadControl>>,
+) {
+    let Some(gamepad) = gamepads.iter().next() else {
+        return;
+    };
+    let stick = |x, y| {
+        Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, x)).unwrap_or(0.0),
+            axes.get(GamepadAxis::new(gamepad, y)).unwrap_or(0.0),
+        )
+    };
+    let button = |button_type| GamepadButton::new(gamepad, button_type);
+
+    let movement = stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY);
+    let aim_stick = stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY);
+
+    for (transform, mut input) in player_query.iter_mut() {
+        let position = transform.translation.truncate();
+        // 右スティックを倒していなければ、前に狙っていた向きのまま
+        let direction = if aim_stick.length() > GAMEPAD_AIM_DEADZONE {
+            Some(aim_stick.normalize())
         } else {
-            velocity.0 = Vec2::ZERO;
+            input
+                .aim
+                .and_then(|aim| (aim - position).try_normalize())
+                .or_else(|| movement.try_normalize())
+        };
+
+        input.latch(PlayerInput {
+            movement: movement.clamp_length_max(1.0),
+            aim: direction.map(|direction| position + direction * GAMEPAD_AIM_DISTANCE),
+            fire: buttons.pressed(button(GamepadButtonType::RightTrigger2)),
+            fire_released: buttons.just_released(button(GamepadButtonType::RightTrigger2)),
+            squid: buttons.pressed(button(GamepadButtonType::LeftTrigger2)),
+            sub_weapon: buttons.just_pressed(button(GamepadButtonType::RightTrigger)),
+            special: buttons.just_pressed(button(GamepadButtonType::North)),
+            switch_weapon: buttons.just_pressed(button(GamepadButtonType::West)),
+        });
+    }
+}
+
+// 画面上のカーソル位置をそのカメラから見たワールド座標に直す。
+// viewport_to_world_2d はビューポートの左上からの位置をとるので、分割画面ではずらしてから渡す
+pub fn cursor_to_world(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor: Vec2,
+) -> Option<Vec2> {
+    let origin = camera
+        .logical_viewport_rect()
+        .map_or(Vec2::ZERO, |rect| rect.min);
+    camera.viewport_to_world_2d(camera_transform, cursor - origin)
+}
+
+// イカ入力の間はイカ状態になり、自チームのインクに潜る
+pub fn squid_form_toggle(
+    mut player_query: Query<(&Transform, &Player, &PlayerInput, &mut SquidForm)>,
+    game_state: Res<GameState>,
+) {
+    for (transform, player, input, mut squid) in player_query.iter_mut() {
+        let holding = input.squid;
+        let terrain = game_state.terrain_at(transform.translation.truncate(), player.team);
+        let submerged = holding && terrain == Terrain::OwnInk;
+
+        if squid.active != holding || squid.submerged != submerged {
+            squid.active = holding;
+            squid.submerged = submerged;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn player_movement(
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &Player,
+            &PlayerInput,
+            &SquidForm,
+            Option<&RainmakerCarrier>,
+        ),
+        Without<Splatted>,
+    >,
+    game_state: Res<GameState>,
+    arena: Res<Arena>,
+    terrain_modifiers: Res<TerrainModifiers>,
+    time: Res<Time>,
+) {
+    for (mut transform, mut velocity, player, input, squid, carrier) in player_query.iter_mut() {
+        // 足元のタイルに応じて速度を変える
+        let terrain = game_state.terrain_at(transform.translation.truncate(), player.team);
+        let mut speed = terrain_modifiers.speed(terrain, squid.active);
+        // ガチホコを持っていると遅くなる
+        if carrier.is_some() {
+            speed *= RAINMAKER_CARRIER_SPEED;
         }
 
-        transform.translation.x += velocity.0.x * time.delta_seconds();
-        transform.translation.y += velocity.0.y * time.delta_seconds();
+        velocity.0 = input.movement.normalize_or_zero() * speed;
+
+        // 壁や場外には入れず、壁に沿って滑る。イカなら自チームの色の壁を登れる
+        let position = quantize_position(arena.slide(
+            transform.translation.truncate(),
+            velocity.0 * time.delta_seconds(),
+            PLAYER_COLLISION_HALF_SIZE,
+            |tile| {
+                squid.active
+                    && arena.is_wall(tile)
+                    && game_state
+                        .painted_walls
+                        .get(&tile)
+                        .and_then(|color| Team::from_color(*color))
+                        == Some(player.team)
+            },
+        ));
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn local_shooting(
-    mut commands: Commands,
-    mouse_button_input: Res<Input<MouseButton>>,
-    windows: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    player_query: Query<(&Transform, &Player), With<Player>>,
+    mut player_query: Query<
+        (
+            &Transform,
+            &Velocity,
+            &Player,
+            &PlayerInput,
+            &Weapon,
+            &mut InkTank,
+            &mut FireCooldown,
+            &mut ChargeState,
+            &mut RollState,
+            &SquidForm,
+        ),
+        Without<Splatted>,
+    >,
+    mut shot_events: EventWriter<ShotFiredEvent>,
+    mut rng: ResMut<SimulationRng>,
+    time: Res<Time>,
 ) {
-    if !mouse_button_input.just_pressed(MouseButton::Left) {
-        return;
-    }
+    for (
+        transform,
+        velocity,
+        player,
+        input,
+        weapon,
+        mut ink_tank,
+        mut cooldown,
+        mut charge,
+        mut roll,
+        squid,
+    ) in player_query.iter_mut()
+    {
+        cooldown.tick(time.delta_seconds());
 
-    let window = windows.single();
-    let (camera, camera_transform) = camera_query.single();
-
-    if let Some(cursor_position) = window.cursor_position() {
-        if let Some(world_position) = camera.viewport_to_world_2d(camera_transform, cursor_position)
-        {
-            for (player_transform, player) in player_query.iter() {
-                let direction =
-                    (world_position - player_transform.translation.truncate()).normalize();
-
-                // ローカルエフェクト用の弾丸を生成
-                commands.spawn((
-                    Projectile {
-                        owner: player.id,
-                        color: Color::BLUE,
-                        lifetime: 3.0,
-                    },
-                    SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::BLUE,
-                            custom_size: Some(Vec2::new(8.0, 8.0)),
-                            ..default()
-                        },
-                        transform: Transform::from_translation(player_transform.translation),
-                        ..default()
-                    },
-                    Velocity(direction * 400.0),
-                ));
+        // イカ状態では撃てず、溜めも解除される
+        if squid.active {
+            charge.charge = 0.0;
+            roll.last_position = None;
+            continue;
+        }
+
+        // 狙う先がない（カーソルが画面外など）間は撃たない
+        let Some(world_position) = input.aim else {
+            continue;
+        };
+        let pressed = input.fire;
+        let just_released = input.fire_released;
+        let stats = &weapon.stats;
+        let origin = transform.translation.truncate();
+        let aim = (world_position - origin).normalize_or_zero();
+        // 自分の真上を狙っていると向きが決まらないので撃たない（ローラーは進む向きに塗る）
+        if aim == Vec2::ZERO && stats.kind != WeaponKind::Roller {
+            continue;
+        }
+        let mut shot = ShotFiredEvent {
+            shooter: player.id,
+            team: player.team,
+            weapon: weapon.id.clone(),
+            kind: stats.kind,
+            origin,
+            direction: aim,
+            charge: 0.0,
+            ink: ink_tank.ink,
+        };
+
+        match stats.kind {
+            WeaponKind::Shooter | WeaponKind::Blaster if stats.charge_time <= 0.0 => {
+                // 押している間は fire_rate の間隔で撃ち続ける
+                if !pressed || !cooldown.ready() || ink_tank.is_low(stats.ink_cost) {
+                    continue;
+                }
+                cooldown.restart(stats.fire_rate);
+
+                // 拡散角の範囲でランダムにぶれる
+                let spread = stats.spread.to_radians() * (rng.f32() - 0.5);
+                shot.direction = Vec2::from_angle(spread).rotate(aim);
+                ink_tank.consume(stats.ink_cost);
+                shot_events.send(shot);
             }
+            WeaponKind::Roller => {
+                // 押している間、半タイル進むごとに塗る
+                if !pressed || velocity.0 == Vec2::ZERO {
+                    roll.last_position = None;
+                    continue;
+                }
+                let moved_enough = roll
+                    .last_position
+                    .is_none_or(|last| last.distance(origin) >= TILE_SIZE / 2.0);
+                if !moved_enough || ink_tank.is_low(stats.ink_cost) {
+                    continue;
+                }
+
+                roll.last_position = Some(origin);
+                shot.direction = velocity.0.normalize();
+                ink_tank.consume(stats.ink_cost);
+                shot_events.send(shot);
+            }
+            WeaponKind::Shooter | WeaponKind::Charger | WeaponKind::Blaster => {
+                // 押している間に溜め、離した時に撃つ（溜めるブラスターも同じ）
+                if !cooldown.ready() {
+                    continue;
+                }
+                if pressed && !ink_tank.is_low(stats.ink_cost) {
+                    charge.charge = (charge.charge + time.delta_seconds()).min(stats.charge_time);
+                    continue;
+                }
+                if !just_released || charge.charge <= 0.0 {
+                    charge.charge = 0.0;
+                    continue;
+                }
+
+                shot.charge = if stats.charge_time > 0.0 {
+                    charge.charge / stats.charge_time
+                } else {
+                    1.0
+                };
+                charge.charge = 0.0;
+                cooldown.restart(stats.fire_rate);
+                ink_tank.consume(stats.ink_cost);
+                shot_events.send(shot);
+            }
+        }
+    }
+}
+
+// 狙っている方向へサブウェポンを投げる
+#[allow(clippy::type_complexity)]
+pub fn local_sub_weapon(
+    mut player_query: Query<
+        (
+            &Transform,
+            &Player,
+            &PlayerInput,
+            &SubWeapon,
+            &mut InkTank,
+            &SquidForm,
+        ),
+        Without<Splatted>,
+    >,
+    mut throw_events: EventWriter<SubWeaponThrownEvent>,
+) {
+    for (transform, player, input, sub_weapon, mut ink_tank, squid) in player_query.iter_mut() {
+        let (true, Some(world_position)) = (input.sub_weapon, input.aim) else {
+            continue;
+        };
+        let stats = &sub_weapon.stats;
+        if squid.active || ink_tank.is_low(stats.ink_cost) {
+            continue;
         }
+
+        // 投げられる距離は throw_range まで
+        let origin = transform.translation.truncate();
+        let target = origin + (world_position - origin).clamp_length_max(stats.throw_range);
+
+        throw_events.send(SubWeaponThrownEvent {
+            thrower: player.id,
+            team: player.team,
+            sub_weapon: sub_weapon.id.clone(),
+            origin,
+            target,
+            ink: ink_tank.ink,
+        });
+        ink_tank.consume(stats.ink_cost);
     }
 }
 
 pub fn paint_system(
     mut commands: Commands,
     mut projectile_query: Query<(Entity, &mut Transform, &Velocity, &mut Projectile)>,
-    mut ground_query: Query<
-        (&mut PaintColor, &mut Sprite, &Transform),
-        (With<PaintableGround>, Without<Projectile>),
-    >,
+    mut painter: GroundPainter,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+    arena: Res<Arena>,
     time: Res<Time>,
 ) {
     for (entity, mut transform, velocity, mut projectile) in projectile_query.iter_mut() {
-        transform.translation.x += velocity.0.x * time.delta_seconds();
-        transform.translation.y += velocity.0.y * time.delta_seconds();
+        let moved =
+            quantize_position(transform.translation.truncate() + velocity.0 * time.delta_seconds());
+        transform.translation.x = moved.x;
+        transform.translation.y = moved.y;
+
+        let direction = velocity.0.normalize_or_zero();
+        let traveled = projectile
+            .origin
+            .distance(transform.translation.truncate())
+            .min(projectile.range);
 
-        projectile.lifetime -= time.delta_seconds();
+        // 飛んでいる間は弾道に沿ってインクを垂らす
+        while projectile.next_drip <= traveled {
+            let drip = projectile.origin + direction * projectile.next_drip;
+            if painter.paint_at(drip, projectile.color) {
+                painter.credit(projectile.owner, 1);
+            }
+            projectile.next_drip += PROJECTILE_DRIP_INTERVAL;
+        }
 
-        if projectile.lifetime <= 0.0 {
-            commands.entity(entity).despawn();
+        // 高い位置にあるほど大きく見せる
+        let position = transform.translation.truncate();
+        transform.scale =
+            Vec3::splat(1.0 + projectile.height(position) / PROJECTILE_ARC_HEIGHT * 0.5);
+
+        let hit_wall = arena.blocks_projectile(position);
+        if traveled < projectile.range && !hit_wall {
             continue;
         }
 
-        // 地面との衝突判定とペイント
-        for (mut paint_color, mut sprite, ground_transform) in ground_query.iter_mut() {
-            let distance = transform.translation.distance(ground_transform.translation);
-            if distance < 16.0 {
-                paint_color.0 = projectile.color;
-                sprite.color = projectile.color;
-                commands.entity(entity).despawn();
-                break;
-            }
+        // 射程の終わりか壁で着弾する。ブラスターは爆発、それ以外はその場に塗り広がる
+        let landing = if hit_wall {
+            painter.paint_at(position, projectile.color);
+            position - direction * TILE_SIZE / 2.0
+        } else {
+            projectile.origin + direction * projectile.range
+        };
+        if projectile.explosion_radius > 0.0 {
+            explosion_events.send(ExplosionEvent {
+                owner: projectile.owner,
+                team: projectile.team,
+                position: landing,
+                radius: projectile.explosion_radius,
+                damage: projectile.damage / 2,
+                spared: Vec::new(),
+            });
+        } else {
+            let inked = painter.paint_circle(landing, projectile.paint_radius, projectile.color);
+            painter.credit(projectile.owner, inked);
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+// インクの自然回復。自チームのインクの上では速く回復する
+pub fn refill_ink(
+    mut player_query: Query<(&Transform, &Player, &SquidForm, &mut InkTank)>,
+    game_state: Res<GameState>,
+    time: Res<Time>,
+) {
+    for (transform, player, squid, mut ink_tank) in player_query.iter_mut() {
+        let terrain = game_state.terrain_at(transform.translation.truncate(), player.team);
+
+        let rate = if squid.submerged {
+            INK_REFILL_SUBMERGED_PER_SECOND
+        } else if terrain == Terrain::OwnInk {
+            INK_REFILL_ON_OWN_INK_PER_SECOND
+        } else {
+            INK_REFILL_PER_SECOND
+        };
+        ink_tank.refill(rate * time.delta_seconds());
+    }
+}
+
+// 敵インクの上では少しずつダメージを受ける
+#[allow(clippy::type_complexity)]
+pub fn enemy_ink_damage(
+    mut player_query: Query<
+        (&Transform, &Player, &mut Health, &mut InkDamage),
+        (Without<Splatted>, Without<SpawnProtection>),
+    >,
+    game_state: Res<GameState>,
+    terrain_modifiers: Res<TerrainModifiers>,
+    time: Res<Time>,
+) {
+    for (transform, player, mut health, mut ink_damage) in player_query.iter_mut() {
+        let terrain = game_state.terrain_at(transform.translation.truncate(), player.team);
+        if terrain != Terrain::EnemyInk || health.0 <= terrain_modifiers.enemy_ink_health_floor {
+            ink_damage.accumulated = 0.0;
+            continue;
+        }
+
+        ink_damage.accumulated +=
+            terrain_modifiers.enemy_ink_damage_per_second * time.delta_seconds();
+        let damage = ink_damage.accumulated.floor();
+        if damage >= 1.0 {
+            ink_damage.accumulated -= damage;
+            health.0 = (health.0 - damage as i32).max(terrain_modifiers.enemy_ink_health_floor);
+        }
+    }
+}
+
+// 自チームのインクに潜っている間と、自陣のリスポーン地点の周りでは少しずつ体力が戻る
+pub fn regenerate_health(
+    mut player_query: Query<
+        (
+            &Transform,
+            &Player,
+            &SquidForm,
+            &mut Health,
+            &mut HealthRegen,
+        ),
+        Without<Splatted>,
+    >,
+    arena: Res<Arena>,
+    time: Res<Time>,
+) {
+    for (transform, player, squid, mut health, mut regen) in player_query.iter_mut() {
+        let position = transform.translation.truncate();
+        let at_spawn = arena.spawn_point(player.team).distance(position) <= SPAWN_AREA_RADIUS;
+        if health.0 >= MAX_HEALTH || !(squid.submerged || at_spawn) {
+            regen.accumulated = 0.0;
+            continue;
+        }
+
+        regen.accumulated += HEALTH_REGEN_PER_SECOND * time.delta_seconds();
+        let healed = regen.accumulated.floor();
+        if healed >= 1.0 {
+            regen.accumulated -= healed;
+            health.0 = (health.0 + healed as i32).min(MAX_HEALTH);
+        }
+    }
+}
+
+// イカ状態では小さく、潜っている間は自分にだけ薄く見える。リスポーン直後の無敵中は半透明
+pub fn update_squid_sprite(
+    mut player_query: Query<(&Player, &SquidForm, Option<&SpawnProtection>, &mut Sprite)>,
+) {
+    for (player, squid, protection, mut sprite) in player_query.iter_mut() {
+        let size = Some(Vec2::splat(if squid.active { 20.0 } else { 30.0 }));
+        let alpha = if squid.submerged {
+            0.3
+        } else if protection.is_some() {
+            0.5
+        } else {
+            1.0
+        };
+        let color = player.team.color().with_a(alpha);
+        // 見た目が変わるときだけ書き換えて、毎フレームの変更検知を避ける
+        if sprite.custom_size != size || sprite.color != color {
+            sprite.custom_size = size;
+            sprite.color = color;
+        }
+    }
+}
+
+// GameState.players をもとに他プレイヤーのスプライトを生成・更新する
+pub fn sync_remote_players(
+    mut commands: Commands,
+    game_state: Res<GameState>,
+    local_query: Query<(&Player, Option<&Bot>)>,
+    mut remote_query: Query<(
+        Entity,
+        &RemotePlayer,
+        &mut Transform,
+        &mut Visibility,
+        Option<&Splatted>,
+    )>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    let local_team = local_query
+        .iter()
+        .find(|(_, bot)| bot.is_none())
+        .map(|(player, _)| player.team);
+    let mut known = std::collections::HashSet::new();
+
+    for (entity, remote, mut transform, mut visibility, splatted) in remote_query.iter_mut() {
+        let Some(state) = game_state.players.get(&remote.id) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        known.insert(remote.id);
+
+        transform.translation.x = state.position.x;
+        transform.translation.y = state.position.y;
+        // やられている間と、潜っている敵は見えない
+        *visibility = if splatted.is_some() || (state.submerged && Some(state.team) != local_team) {
+            Visibility::Hidden
+        } else {
+            Visibility::Visible
+        };
+    }
+
+    for (id, state) in game_state.players.iter() {
+        // 自分とボットはこのクライアントで動かしている
+        if local_query.iter().any(|(player, _)| player.id == *id) || known.contains(id) {
+            continue;
         }
+
+        commands.spawn((
+            RemotePlayer {
+                id: *id,
+                team: state.team,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: state.team.color(),
+                    custom_size: Some(Vec2::new(30.0, 30.0)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(state.position.x, state.position.y, 1.0),
+                ..default()
+            },
+        ));
     }
 }
 
 pub fn camera_follow(
-    player_query: Query<&Transform, (With<Player>, Without<Camera>)>,
-    mut camera_query: Query<&mut Transform, (With<Camera>, Without<Player>)>,
+    player_query: Query<&Transform, (With<Player>, Without<PlayerCamera>)>,
+    mut camera_query: Query<(&mut Transform, &PlayerCamera)>,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        if let Ok(mut camera_transform) = camera_query.get_single_mut() {
+    for (mut camera_transform, player_camera) in camera_query.iter_mut() {
+        if let Ok(player_transform) = player_query.get(player_camera.player) {
             camera_transform.translation.x = player_transform.translation.x;
             camera_transform.translation.y = player_transform.translation.y;
         }
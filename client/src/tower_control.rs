@@ -0,0 +1,178 @@
+use crate::components::*;
+use crate::map::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+const TOWER_COLOR: Color = Color::rgb(0.9, 0.85, 0.5);
+
+// 試合開始時にマップの通り道の中央へヤグラを置く。
+// Tower の目印と重なる通り道の位置がチェックポイントになる
+pub fn reset_tower_control(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    mut tower_control: ResMut<TowerControl>,
+    tower_query: Query<Entity, With<TowerMarker>>,
+) {
+    for entity in tower_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let path = arena
+        .map
+        .tower_path
+        .iter()
+        .map(|tile| tile_to_world(*tile))
+        .collect::<Vec<_>>();
+    if path.len() < 2 {
+        warn!("Map {} has no tower path", arena.map.name);
+    }
+
+    let mut tower = TowerControl::new(path);
+    let checkpoints = arena
+        .map
+        .objectives
+        .iter()
+        .filter(|objective| objective.kind == ObjectiveKind::Tower)
+        .filter_map(|objective| {
+            // 通り道を半タイルずつたどり、目印に入っている区間の中央を使う
+            let inside = (0..=(tower.length / (TILE_SIZE / 2.0)) as i32)
+                .map(|step| step as f32 * TILE_SIZE / 2.0)
+                .filter(|distance| objective.contains(world_to_tile(tower.point_at(*distance))))
+                .collect::<Vec<_>>();
+            let first = inside.first()?;
+            let last = inside.last()?;
+            Some((first + last) / 2.0)
+        })
+        .collect();
+    tower.set_checkpoints(checkpoints);
+
+    let position = tower.position();
+    commands.spawn((
+        TowerMarker,
+        SpriteBundle {
+            sprite: Sprite {
+                color: TOWER_COLOR,
+                custom_size: Some(Vec2::splat(TOWER_RIDE_RADIUS * 1.5)),
+                ..default()
+            },
+            transform: Transform::from_xyz(position.x, position.y, 0.8),
+            ..default()
+        },
+    ));
+    *tower_control = tower;
+}
+
+// 乗っているプレイヤーのチームでヤグラを進める。両チームが乗っていれば止まる
+pub fn update_tower_control(
+    mut tower_control: ResMut<TowerControl>,
+    player_query: Query<(&Transform, &Player), Without<Splatted>>,
+    remote_query: Query<(&Transform, &RemotePlayer), Without<Splatted>>,
+    mut next_phase: ResMut<NextState<MatchPhase>>,
+    time: Res<Time>,
+) {
+    if tower_control.path.len() < 2 {
+        return;
+    }
+
+    let tower = tower_control.position();
+    let local = player_query
+        .iter()
+        .map(|(transform, player)| (transform, player.team));
+    let remote = remote_query
+        .iter()
+        .map(|(transform, remote)| (transform, remote.team));
+    let riders = local
+        .chain(remote)
+        .filter(|(transform, _)| {
+            transform.translation.truncate().distance(tower) <= TOWER_RIDE_RADIUS
+        })
+        .map(|(_, team)| team)
+        .collect::<Vec<_>>();
+
+    let blue = riders.contains(&Team::Blue);
+    let orange = riders.contains(&Team::Orange);
+    tower_control.contested = blue && orange;
+    tower_control.controller = match (blue, orange) {
+        (true, false) => Some(Team::Blue),
+        (false, true) => Some(Team::Orange),
+        _ => None,
+    };
+
+    if let Some(team) = tower_control.controller {
+        tower_control.push(team, time.delta_seconds());
+    }
+
+    if let Some(team) = tower_control.finished_by() {
+        info!("💥 {} team delivered the tower!", team.name());
+        next_phase.set(MatchPhase::Finished);
+    }
+}
+
+pub fn update_tower_sprite(
+    tower_control: Res<TowerControl>,
+    mut tower_query: Query<(&mut Transform, &mut Sprite), With<TowerMarker>>,
+) {
+    if !tower_control.is_changed() {
+        return;
+    }
+
+    let position = tower_control.position();
+    for (mut transform, mut sprite) in tower_query.iter_mut() {
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        sprite.color = tower_control
+            .controller
+            .map_or(TOWER_COLOR, |team| team.color());
+    }
+}
+
+pub fn tally_tower_control(tower_control: Res<TowerControl>, mut result: ResMut<MatchResult>) {
+    let winner = tower_control
+        .finished_by()
+        .or_else(|| tower_control.leader());
+
+    *result = MatchResult {
+        winner,
+        scores: [Team::Blue, Team::Orange]
+            .into_iter()
+            .map(|team| {
+                let percent = tower_control.best(team) / (tower_control.length / 2.0).max(1.0);
+                (team, format!("{:.0}% pushed", percent * 100.0))
+            })
+            .collect(),
+        ..default()
+    };
+}
+
+pub fn update_tower_text(
+    tower_control: Res<TowerControl>,
+    mut text_query: Query<&mut Text, With<ModeScoreText>>,
+) {
+    let half = (tower_control.length / 2.0).max(1.0);
+    let mut label = [Team::Blue, Team::Orange]
+        .into_iter()
+        .map(|team| {
+            format!(
+                "{} {:.0}%",
+                team.name().to_uppercase(),
+                tower_control.best(team) / half * 100.0
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("   |   ");
+
+    if tower_control.contested {
+        label.push_str("   CONTESTED");
+    } else if tower_control
+        .checkpoint_wait
+        .is_some_and(|(team, _)| tower_control.controller == Some(team))
+    {
+        label.push_str("   CHECKPOINT");
+    } else if let Some(team) = tower_control.controller {
+        label.push_str(&format!("   {} on tower", team.name()));
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = label.clone();
+    }
+}
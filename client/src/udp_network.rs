@@ -1,5 +1,9 @@
 use crate::components::*;
+use crate::events::*;
+use crate::paint::*;
 use crate::resources::*;
+use crate::weapons::*;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use serde_json::json;
 use std::net::SocketAddr;
@@ -8,40 +12,116 @@ use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use tokio::net::UdpSocket;
 
-pub fn setup_udp_network(mut network_client: ResMut<NetworkClient>) {
-    if network_client.connected {
+pub fn setup_udp_network(mut network_client: ResMut<NetworkClient>, game_mode: Res<GameMode>) {
+    if network_client.connected || network_client.offline {
         return;
     }
 
-    let (to_server_tx, to_server_rx) = mpsc::channel::<String>();
+    connect_udp(&mut network_client, *game_mode);
+}
+
+// ロビーで O キーを押すと、オフラインの練習モードとサーバーに繋ぐモードを切り替える
+#[allow(clippy::type_complexity)]
+pub fn toggle_offline(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut network_client: ResMut<NetworkClient>,
+    mut game_state: ResMut<GameState>,
+    game_mode: Res<GameMode>,
+    extra_players: Query<&Player, Or<(With<GamepadControl>, With<Bot>)>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::O) {
+        return;
+    }
+
+    network_client.offline = !network_client.offline;
+    if !network_client.offline {
+        connect_udp(&mut network_client, *game_mode);
+        for player in extra_players.iter() {
+            connect_extra_udp(&mut network_client, player.id, player.team, *game_mode);
+        }
+        return;
+    }
+
+    // 送信側を閉じると通信スレッドも終わる
+    network_client.sender = None;
+    network_client.receiver = None;
+    network_client.extra_senders.clear();
+    network_client.connected = false;
+    game_state.players.clear();
+    info!("📴 Offline practice mode");
+}
+
+fn connect_udp(network_client: &mut NetworkClient, mode: GameMode) {
     let (from_server_tx, from_server_rx) = mpsc::channel::<String>();
 
     let player_id = network_client.player_id;
+    let to_server_tx = spawn_udp_thread(
+        player_id,
+        Team::Blue,
+        mode,
+        network_client.host,
+        Some(from_server_tx),
+    );
+
+    network_client.sender = Some(to_server_tx);
+    network_client.receiver = Some(Arc::new(Mutex::new(from_server_rx)));
+    network_client.connected = true;
+
+    info!(
+        "UDP network connection established with player ID: {}",
+        player_id
+    );
+}
+
+// 分割画面の 2P などを、自分とは別のクライアントとしてサーバーに参加させる。
+// サーバーからの配信は自分の接続で受け取っているので、こちらで受け取ったものは捨てる
+pub fn connect_extra_udp(
+    network_client: &mut NetworkClient,
+    player_id: uuid::Uuid,
+    team: Team,
+    mode: GameMode,
+) {
+    let to_server_tx = spawn_udp_thread(player_id, team, mode, false, None);
+    network_client.extra_senders.insert(player_id, to_server_tx);
+
+    info!(
+        "UDP network connection established for local player ID: {}",
+        player_id
+    );
+}
+
+fn spawn_udp_thread(
+    player_id: uuid::Uuid,
+    team: Team,
+    mode: GameMode,
+    host: bool,
+    from_server_tx: Option<mpsc::Sender<String>>,
+) -> mpsc::Sender<String> {
+    let (to_server_tx, to_server_rx) = mpsc::channel::<String>();
 
     // UDP通信を別スレッドで処理
     thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async {
-            if let Err(e) = handle_udp_connection(player_id, to_server_rx, from_server_tx).await {
+            if let Err(e) =
+                handle_udp_connection(player_id, team, mode, host, to_server_rx, from_server_tx)
+                    .await
+            {
                 eprintln!("UDP error: {}", e);
             }
         });
     });
 
-    network_client.sender = Some(to_server_tx);
-    network_client.receiver = Some(Arc::new(Mutex::new(from_server_rx)));
-    network_client.connected = true;
-
-    info!(
-        "UDP network connection established with player ID: {}",
-        player_id
-    );
+    to_server_tx
 }
 
 async fn handle_udp_connection(
     player_id: uuid::Uuid,
+    team: Team,
+    mode: GameMode,
+    host: bool,
     to_server_rx: mpsc::Receiver<String>,
-    from_server_tx: mpsc::Sender<String>,
+    from_server_tx: Option<mpsc::Sender<String>>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // UDPソケットをバインド（任意のポート）
     let socket = UdpSocket::bind("0.0.0.0:0").await?;
@@ -49,12 +129,14 @@ async fn handle_udp_connection(
 
     println!("Connected to UDP server at {}", server_addr);
 
-    // ゲーム参加メッセージを送信
+    // ゲーム参加メッセージを送信（協力モードではサーバーがチームの人数を制限する）
     let join_message = json!({
         "type": "join_game",
         "game_id": "default",
         "player_id": player_id.to_string(),
-        "team": "blue"
+        "team": team.name(),
+        "mode": mode.name(),
+        "host": host
     });
 
     // 送信タスク用のソケットを作成
@@ -77,11 +159,15 @@ async fn handle_udp_connection(
 
     // 受信タスク
     let receive_handle = tokio::spawn(async move {
-        let mut buffer = [0; 1024];
+        // 協力モードの敵の一覧が入るよう大きめにとる
+        let mut buffer = [0; 8192];
 
         loop {
             match socket_recv.recv_from(&mut buffer).await {
                 Ok((len, _addr)) => {
+                    let Some(from_server_tx) = &from_server_tx else {
+                        continue;
+                    };
                     if let Ok(message) = String::from_utf8(buffer[..len].to_vec()) {
                         if let Err(e) = from_server_tx.send(message) {
                             eprintln!("Failed to forward message to game: {}", e);
@@ -106,69 +192,490 @@ async fn handle_udp_connection(
     Ok(())
 }
 
+// このクライアントで動かしているプレイヤー（自分とボット）。
+// 自分たちの行動だけを送り、サーバーから戻ってきた自分たちの行動は無視する
+#[derive(SystemParam)]
+pub struct LocalPlayers<'w, 's> {
+    players: Query<'w, 's, &'static Player>,
+}
+
+impl<'w, 's> LocalPlayers<'w, 's> {
+    pub fn contains(&self, id: uuid::Uuid) -> bool {
+        self.players.iter().any(|player| player.id == id)
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub fn send_player_position_udp(
-    player_query: Query<&Transform, (With<Player>, Changed<Transform>)>,
+    player_query: Query<
+        (&Transform, &Player, &SquidForm),
+        Or<(Changed<SimulatedPosition>, Changed<SquidForm>)>,
+    >,
     network_client: Res<NetworkClient>,
+    tick: Res<SimulationTick>,
 ) {
-    if let Some(sender) = &network_client.sender {
-        for transform in player_query.iter() {
-            let message = json!({
-                "type": "player_move",
-                "position": {
-                    "x": transform.translation.x,
-                    "y": transform.translation.y
-                }
-            });
+    // 2P やボットは自分の接続から送る
+    for (transform, player, squid) in player_query.iter() {
+        let Some(sender) = network_client.sender_for(player.id) else {
+            continue;
+        };
+        let message = json!({
+            "type": "player_move",
+            "tick": tick.0,
+            "player_id": player.id.to_string(),
+            "position": {
+                "x": transform.translation.x,
+                "y": transform.translation.y
+            },
+            "squid": squid.active,
+            "submerged": squid.submerged
+        });
 
-            if let Err(e) = sender.send(message.to_string()) {
-                warn!("Failed to send position update: {}", e);
-            }
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send position update: {}", e);
         }
     }
 }
 
+// 自分の射撃を武器の種類ごとのメッセージでサーバーへ送る
 pub fn send_shoot_action_udp(
-    mouse_button_input: Res<Input<MouseButton>>,
-    windows: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    player_query: Query<&Transform, With<Player>>,
+    mut shot_events: EventReader<ShotFiredEvent>,
+    weapon_registry: Res<WeaponRegistry>,
     network_client: Res<NetworkClient>,
+    local_players: LocalPlayers,
+    tick: Res<SimulationTick>,
 ) {
-    if !mouse_button_input.just_pressed(MouseButton::Left) {
+    if network_client.sender.is_none() {
+        shot_events.clear();
         return;
     }
 
-    if let Some(sender) = &network_client.sender {
-        let window = windows.single();
-        let (camera, camera_transform) = camera_query.single();
-
-        if let Some(cursor_position) = window.cursor_position() {
-            if let Some(world_position) =
-                camera.viewport_to_world_2d(camera_transform, cursor_position)
-            {
-                for player_transform in player_query.iter() {
-                    let direction =
-                        (world_position - player_transform.translation.truncate()).normalize();
-
-                    let message = json!({
-                        "type": "player_shoot",
-                        "direction": {
-                            "x": direction.x,
-                            "y": direction.y
-                        }
-                    });
+    for shot in shot_events.read() {
+        if !local_players.contains(shot.shooter) {
+            continue;
+        }
+        let Some(sender) = network_client.sender_for(shot.shooter) else {
+            continue;
+        };
 
-                    if let Err(e) = sender.send(message.to_string()) {
-                        warn!("Failed to send shoot action: {}", e);
-                    }
-                    break;
-                }
+        let stats = weapon_registry.get(&shot.weapon);
+        let stroke = PaintStroke::of_shot(shot, &stats);
+        let message = json!({
+            "type": shot.kind.message_type(),
+            "tick": tick.0,
+            "player_id": shot.shooter.to_string(),
+            "team": shot.team.name(),
+            "weapon": shot.weapon,
+            "position": {
+                "x": shot.origin.x,
+                "y": shot.origin.y
+            },
+            "direction": {
+                "x": shot.direction.x,
+                "y": shot.direction.y
+            },
+            "charge": shot.charge,
+            "ink": shot.ink,
+            "ink_cost": stats.ink_cost,
+            "paint": {
+                "from": {"x": stroke.from.x, "y": stroke.from.y},
+                "to": {"x": stroke.to.x, "y": stroke.to.y},
+                "radius": stroke.radius,
+                "splash": stroke.splash
             }
+        });
+
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send shoot action: {}", e);
+        }
+    }
+}
+
+pub fn send_sub_weapon_udp(
+    mut throw_events: EventReader<SubWeaponThrownEvent>,
+    sub_weapon_registry: Res<SubWeaponRegistry>,
+    network_client: Res<NetworkClient>,
+    local_players: LocalPlayers,
+    tick: Res<SimulationTick>,
+) {
+    if network_client.sender.is_none() {
+        throw_events.clear();
+        return;
+    }
+
+    for throw in throw_events.read() {
+        if !local_players.contains(throw.thrower) {
+            continue;
+        }
+        let Some(sender) = network_client.sender_for(throw.thrower) else {
+            continue;
+        };
+
+        // サーバーが同じ範囲を塗れるよう爆発半径と導火線も送る
+        let stats = sub_weapon_registry.get(&throw.sub_weapon);
+        let message = json!({
+            "type": "sub_weapon_throw",
+            "tick": tick.0,
+            "player_id": throw.thrower.to_string(),
+            "team": throw.team.name(),
+            "sub_weapon": throw.sub_weapon,
+            "position": {
+                "x": throw.origin.x,
+                "y": throw.origin.y
+            },
+            "target": {
+                "x": throw.target.x,
+                "y": throw.target.y
+            },
+            "explosion_radius": stats.explosion_radius,
+            "fuse_time": stats.flight_time + stats.fuse_time,
+            "ink": throw.ink,
+            "ink_cost": stats.ink_cost
+        });
+
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send sub weapon throw: {}", e);
+        }
+    }
+}
+
+pub fn send_special_udp(
+    mut special_events: EventReader<SpecialActivatedEvent>,
+    network_client: Res<NetworkClient>,
+    local_players: LocalPlayers,
+    tick: Res<SimulationTick>,
+) {
+    if network_client.sender.is_none() {
+        special_events.clear();
+        return;
+    }
+
+    for event in special_events.read() {
+        if !local_players.contains(event.player) {
+            continue;
+        }
+        let Some(sender) = network_client.sender_for(event.player) else {
+            continue;
+        };
+
+        let message = json!({
+            "type": "special_activated",
+            "tick": tick.0,
+            "player_id": event.player.to_string(),
+            "team": event.team.name(),
+            "special": "ink_armor",
+            "duration": INK_ARMOR_SECONDS
+        });
+
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send special activation: {}", e);
+        }
+    }
+}
+
+// ガチホコを拾ったことを送る。落としたことは player_splatted で伝わる
+pub fn send_rainmaker_pickup_udp(
+    mut pickup_events: EventReader<RainmakerPickedUpEvent>,
+    network_client: Res<NetworkClient>,
+    local_players: LocalPlayers,
+) {
+    if network_client.sender.is_none() {
+        pickup_events.clear();
+        return;
+    }
+
+    for event in pickup_events.read() {
+        if !local_players.contains(event.player) {
+            continue;
+        }
+        let Some(sender) = network_client.sender_for(event.player) else {
+            continue;
+        };
+
+        let message = json!({
+            "type": "rainmaker_pickup",
+            "player_id": event.player.to_string(),
+            "team": event.team.name()
+        });
+
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send rainmaker pickup: {}", e);
+        }
+    }
+}
+
+// ホストが試合の進行を、段階が変わったときと一定間隔で送る
+pub fn send_match_state_udp(
+    mut clock: ResMut<MatchClock>,
+    phase: Res<State<MatchPhase>>,
+    game_mode: Res<GameMode>,
+    rng: Res<SimulationRng>,
+    network_client: Res<NetworkClient>,
+    time: Res<Time>,
+) {
+    let Some(sender) = &network_client.sender else {
+        return;
+    };
+    let due = clock.sync.tick(time.delta()).just_finished();
+    if !due && !phase.is_changed() {
+        return;
+    }
+
+    let message = json!({
+        "type": "match_state",
+        "phase": phase.get().name(),
+        "mode": game_mode.name(),
+        "remaining": clock.remaining(*phase.get()),
+        "seed": rng.seed
+    });
+
+    if let Err(e) = sender.send(message.to_string()) {
+        warn!("Failed to send match state: {}", e);
+    }
+}
+
+// ホストがガチエリアの持ち主とカウントを一定間隔で送る
+pub fn send_splat_zones_state_udp(
+    mut clock: ResMut<MatchClock>,
+    splat_zones: Res<SplatZones>,
+    network_client: Res<NetworkClient>,
+    time: Res<Time>,
+) {
+    let Some(sender) = &network_client.sender else {
+        return;
+    };
+    if !clock.rule_sync.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let zones = splat_zones
+        .zones
+        .iter()
+        .enumerate()
+        .map(|(index, zone)| {
+            json!({
+                "index": index,
+                "owner": zone.owner.map(Team::name)
+            })
+        })
+        .collect::<Vec<_>>();
+    let message = json!({
+        "type": "splat_zones_state",
+        "zones": zones,
+        "controller": splat_zones.controller.map(Team::name),
+        "counts": {
+            "blue": splat_zones.count(Team::Blue),
+            "orange": splat_zones.count(Team::Orange)
+        },
+        "penalties": {
+            "blue": splat_zones.penalty(Team::Blue),
+            "orange": splat_zones.penalty(Team::Orange)
+        }
+    });
+
+    if let Err(e) = sender.send(message.to_string()) {
+        warn!("Failed to send splat zones state: {}", e);
+    }
+}
+
+// ホストがガチヤグラの位置と記録を一定間隔で送る
+pub fn send_tower_state_udp(
+    mut clock: ResMut<MatchClock>,
+    tower_control: Res<TowerControl>,
+    network_client: Res<NetworkClient>,
+    time: Res<Time>,
+) {
+    let Some(sender) = &network_client.sender else {
+        return;
+    };
+    if !clock.rule_sync.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let message = json!({
+        "type": "tower_state",
+        "progress": tower_control.progress,
+        "controller": tower_control.controller.map(Team::name),
+        "contested": tower_control.contested,
+        "best": {
+            "blue": tower_control.best(Team::Blue),
+            "orange": tower_control.best(Team::Orange)
+        },
+        "cleared": tower_control.cleared,
+        "checkpoint_wait": tower_control.checkpoint_wait.map(|(team, remaining)| {
+            json!({"team": team.name(), "remaining": remaining})
+        })
+    });
+
+    if let Err(e) = sender.send(message.to_string()) {
+        warn!("Failed to send tower state: {}", e);
+    }
+}
+
+// ホストがガチホコの位置・シールド・記録を一定間隔で送る
+pub fn send_rainmaker_state_udp(
+    mut clock: ResMut<MatchClock>,
+    rainmaker: Res<Rainmaker>,
+    network_client: Res<NetworkClient>,
+    time: Res<Time>,
+) {
+    let Some(sender) = &network_client.sender else {
+        return;
+    };
+    if !clock.rule_sync.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let message = json!({
+        "type": "rainmaker_state",
+        "position": {"x": rainmaker.position.x, "y": rainmaker.position.y},
+        "shield": rainmaker.shield,
+        "broken_by": rainmaker.broken_by.map(Team::name),
+        "best": {
+            "blue": rainmaker.best(Team::Blue),
+            "orange": rainmaker.best(Team::Orange)
+        }
+    });
+
+    if let Err(e) = sender.send(message.to_string()) {
+        warn!("Failed to send rainmaker state: {}", e);
+    }
+}
+
+// 協力モードのホストが敵・金イクラ・波の状態を一定間隔で送る
+pub fn send_coop_state_udp(
+    mut coop_waves: ResMut<CoopWaves>,
+    enemy_query: Query<(&Enemy, &Transform)>,
+    egg_query: Query<(&Egg, &Transform)>,
+    network_client: Res<NetworkClient>,
+    time: Res<Time>,
+) {
+    let Some(sender) = &network_client.sender else {
+        return;
+    };
+    if !coop_waves.sync_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let enemies = enemy_query
+        .iter()
+        .map(|(enemy, transform)| {
+            json!({
+                "id": enemy.id,
+                "kind": enemy.kind.name(),
+                "x": transform.translation.x,
+                "y": transform.translation.y,
+                "health": enemy.health
+            })
+        })
+        .collect::<Vec<_>>();
+    let eggs = egg_query
+        .iter()
+        .map(|(egg, transform)| {
+            json!({
+                "id": egg.id,
+                "x": transform.translation.x,
+                "y": transform.translation.y
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let message = json!({
+        "type": "coop_state",
+        "wave": coop_waves.wave,
+        "delivered": coop_waves.delivered,
+        "remaining": coop_waves.wave_timer.remaining_secs(),
+        "failed": coop_waves.failed,
+        "cleared": coop_waves.cleared,
+        "enemies": enemies,
+        "eggs": eggs
+    });
+
+    if let Err(e) = sender.send(message.to_string()) {
+        warn!("Failed to send coop state: {}", e);
+    }
+}
+
+pub fn send_egg_events_udp(
+    mut collected_events: EventReader<EggCollectedEvent>,
+    mut deposited_events: EventReader<EggDepositedEvent>,
+    network_client: Res<NetworkClient>,
+    local_players: LocalPlayers,
+) {
+    if network_client.sender.is_none() {
+        collected_events.clear();
+        deposited_events.clear();
+        return;
+    }
+
+    let collected = collected_events
+        .read()
+        .filter(|event| local_players.contains(event.player))
+        .map(|event| {
+            let message = json!({
+                "type": "egg_collected",
+                "player_id": event.player.to_string(),
+                "egg": event.egg
+            });
+            (event.player, message)
+        });
+    let deposited = deposited_events
+        .read()
+        .filter(|event| local_players.contains(event.player))
+        .map(|event| {
+            let message = json!({
+                "type": "egg_deposited",
+                "player_id": event.player.to_string()
+            });
+            (event.player, message)
+        });
+
+    for (player, message) in collected.chain(deposited) {
+        let Some(sender) = network_client.sender_for(player) else {
+            continue;
+        };
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send egg event: {}", e);
         }
     }
 }
 
-pub fn handle_udp_messages(network_client: Res<NetworkClient>, mut _game_state: ResMut<GameState>) {
+// サーバーから届いた他プレイヤーの行動をローカルのイベントに変換して流す
+#[derive(SystemParam)]
+pub struct RemoteActions<'w> {
+    shots: EventWriter<'w, ShotFiredEvent>,
+    throws: EventWriter<'w, SubWeaponThrownEvent>,
+    splats: EventWriter<'w, PlayerSplattedEvent>,
+    specials: EventWriter<'w, SpecialActivatedEvent>,
+    rainmaker_pickups: EventWriter<'w, RainmakerPickedUpEvent>,
+    eggs_collected: EventWriter<'w, EggCollectedEvent>,
+    eggs_deposited: EventWriter<'w, EggDepositedEvent>,
+}
+
+// サーバーから届いた試合の進行とルールの状態を反映する先
+#[derive(SystemParam)]
+pub struct MatchSync<'w> {
+    match_clock: ResMut<'w, MatchClock>,
+    phase: Res<'w, State<MatchPhase>>,
+    next_phase: ResMut<'w, NextState<MatchPhase>>,
+    game_mode: ResMut<'w, GameMode>,
+    splat_zones: ResMut<'w, SplatZones>,
+    tower_control: ResMut<'w, TowerControl>,
+    rainmaker: ResMut<'w, Rainmaker>,
+    coop_waves: ResMut<'w, CoopWaves>,
+    rng: ResMut<'w, SimulationRng>,
+}
+
+pub fn handle_udp_messages(
+    mut network_client: ResMut<NetworkClient>,
+    local_players: LocalPlayers,
+    mut painter: GroundPainter,
+    mut remote_actions: RemoteActions,
+    mut match_sync: MatchSync,
+) {
+    // --host でも、同じゲームに先にホストがいればサーバーに断られる
+    let mut host_granted = None;
+
     if let Some(receiver) = &network_client.receiver {
         if let Ok(receiver_guard) = receiver.try_lock() {
             while let Ok(message) = receiver_guard.try_recv() {
@@ -176,6 +683,13 @@ pub fn handle_udp_messages(network_client: Res<NetworkClient>, mut _game_state:
                     match parsed["type"].as_str() {
                         Some("connected") => {
                             info!("✅ Successfully connected to server");
+                            host_granted = parsed["host"].as_bool();
+                        }
+                        Some("join_rejected") => {
+                            warn!(
+                                "🚫 The server rejected this client: {}",
+                                parsed["reason"].as_str().unwrap_or("unknown")
+                            );
                         }
                         Some("test_response") => {
                             if let Some(msg) = parsed["message"].as_str() {
@@ -223,6 +737,18 @@ pub fn handle_udp_messages(network_client: Res<NetworkClient>, mut _game_state:
                         }
                         Some("game_state") => {
                             info!("📊 Received game state update");
+                            if let Some(players) = parsed["players"].as_array() {
+                                painter.game_state.players = players
+                                    .iter()
+                                    .filter_map(parse_player_state)
+                                    .map(|state| (state.id, state))
+                                    .collect();
+                            }
+                            apply_painted_areas(
+                                &parsed["painted_tiles"],
+                                &local_players,
+                                &mut painter,
+                            );
                         }
                         Some("player_update") => {
                             if let Some(player_id) = parsed["player_id"].as_str() {
@@ -231,11 +757,182 @@ pub fn handle_udp_messages(network_client: Res<NetworkClient>, mut _game_state:
                                     parsed["position"]["y"].as_f64(),
                                 ) {
                                     info!("🏃 Player {} moved to ({}, {})", player_id, x, y);
+                                    let tick = parsed["tick"].as_u64();
+                                    if let Some(state) = uuid::Uuid::parse_str(player_id)
+                                        .ok()
+                                        .and_then(|id| painter.game_state.players.get_mut(&id))
+                                        // UDP で後から届いた古い位置は捨てる
+                                        .filter(|state| {
+                                            !tick.is_some_and(|tick| {
+                                                SimulationTick::is_stale(tick, state.tick)
+                                            })
+                                        })
+                                    {
+                                        state.position = Vec2::new(x as f32, y as f32);
+                                        state.tick = tick.unwrap_or(state.tick);
+                                        state.submerged =
+                                            parsed["submerged"].as_bool().unwrap_or(false);
+                                    }
                                 }
                             }
                         }
                         Some("paint_update") => {
-                            info!("🎨 Paint update received");
+                            apply_painted_areas(
+                                &parsed["painted_areas"],
+                                &local_players,
+                                &mut painter,
+                            );
+                        }
+                        Some(
+                            message_type @ ("player_shoot" | "player_roll" | "charger_shot"
+                            | "blaster_shot"),
+                        ) => {
+                            // 他プレイヤーの射撃も自分の射撃と同じように再現する
+                            if let Some(shot) = parse_shot(message_type, &parsed) {
+                                if !local_players.contains(shot.shooter) {
+                                    remote_actions.shots.send(shot);
+                                }
+                            }
+                        }
+                        Some("sub_weapon_throw") => {
+                            if let Some(throw) = parse_sub_weapon_throw(&parsed) {
+                                if !local_players.contains(throw.thrower) {
+                                    remote_actions.throws.send(throw);
+                                }
+                            }
+                        }
+                        Some("player_hit") => {
+                            // 他プレイヤーの体力は本人のクライアントが判定した値に合わせる
+                            if let (Some(state), Some(health)) = (
+                                parsed["player_id"]
+                                    .as_str()
+                                    .and_then(|id| uuid::Uuid::parse_str(id).ok())
+                                    .and_then(|id| painter.game_state.players.get_mut(&id)),
+                                parsed["health"].as_i64(),
+                            ) {
+                                state.health = health as i32;
+                            }
+                        }
+                        Some("player_splatted") => {
+                            if let (Some(victim), Some(attacker), Some(team), Some(x), Some(y)) = (
+                                parsed["player_id"]
+                                    .as_str()
+                                    .and_then(|id| uuid::Uuid::parse_str(id).ok()),
+                                parsed["attacker_id"]
+                                    .as_str()
+                                    .and_then(|id| uuid::Uuid::parse_str(id).ok()),
+                                parsed["attacker_team"].as_str().and_then(Team::from_name),
+                                parsed["position"]["x"].as_f64(),
+                                parsed["position"]["y"].as_f64(),
+                            ) {
+                                // 自分がやられた通知はローカルで処理済み
+                                if !local_players.contains(victim) {
+                                    remote_actions.splats.send(PlayerSplattedEvent {
+                                        victim,
+                                        attacker,
+                                        attacker_team: team,
+                                        position: Vec2::new(x as f32, y as f32),
+                                    });
+                                }
+                            }
+                        }
+                        Some("special_activated") => {
+                            if let (Some(player), Some(team)) = (
+                                parsed["player_id"]
+                                    .as_str()
+                                    .and_then(|id| uuid::Uuid::parse_str(id).ok()),
+                                parsed["team"].as_str().and_then(Team::from_name),
+                            ) {
+                                if !local_players.contains(player) {
+                                    remote_actions
+                                        .specials
+                                        .send(SpecialActivatedEvent { player, team });
+                                }
+                            }
+                        }
+                        Some("rainmaker_pickup") => {
+                            if let (Some(player), Some(team)) = (
+                                parsed["player_id"]
+                                    .as_str()
+                                    .and_then(|id| uuid::Uuid::parse_str(id).ok()),
+                                parsed["team"].as_str().and_then(Team::from_name),
+                            ) {
+                                if !local_players.contains(player) {
+                                    remote_actions
+                                        .rainmaker_pickups
+                                        .send(RainmakerPickedUpEvent { player, team });
+                                }
+                            }
+                        }
+                        Some("egg_collected") => {
+                            if let (Some(player), Some(egg)) = (
+                                parsed["player_id"]
+                                    .as_str()
+                                    .and_then(|id| uuid::Uuid::parse_str(id).ok()),
+                                parsed["egg"].as_u64(),
+                            ) {
+                                if !local_players.contains(player) {
+                                    remote_actions.eggs_collected.send(EggCollectedEvent {
+                                        player,
+                                        egg: egg as u32,
+                                    });
+                                }
+                            }
+                        }
+                        Some("egg_deposited") => {
+                            if let Some(player) = parsed["player_id"]
+                                .as_str()
+                                .and_then(|id| uuid::Uuid::parse_str(id).ok())
+                            {
+                                if !local_players.contains(player) {
+                                    remote_actions
+                                        .eggs_deposited
+                                        .send(EggDepositedEvent { player });
+                                }
+                            }
+                        }
+                        Some("player_ready") => {
+                            // ロビーで誰かが準備完了したら、ホストがカウントダウンを始めて皆に伝える
+                            if network_client.is_host()
+                                && *match_sync.phase.get() == MatchPhase::Waiting
+                            {
+                                match_sync.next_phase.set(MatchPhase::Countdown);
+                            }
+                        }
+                        Some("match_state") if !network_client.is_host() => {
+                            // ホストの試合進行に合わせる
+                            if let Some(mode) =
+                                parsed["mode"].as_str().and_then(GameMode::from_name)
+                            {
+                                *match_sync.game_mode = mode;
+                            }
+                            // 試合開始で同じシードから乱数を作り直せるようにする。
+                            // 段階を変えるより先に入れるので、Waiting を抜けて作り直すときにはホストのシードになっている
+                            if let Some(seed) = parsed["seed"].as_u64() {
+                                match_sync.rng.seed = seed;
+                            }
+                            if let Some(phase) =
+                                parsed["phase"].as_str().and_then(MatchPhase::from_name)
+                            {
+                                if let Some(remaining) = parsed["remaining"].as_f64() {
+                                    match_sync
+                                        .match_clock
+                                        .sync_remaining(phase, remaining as f32);
+                                }
+                                match_sync.next_phase.set(phase);
+                            }
+                        }
+                        Some("splat_zones_state") if !network_client.is_host() => {
+                            apply_splat_zones_state(&parsed, &mut match_sync.splat_zones);
+                        }
+                        Some("tower_state") if !network_client.is_host() => {
+                            apply_tower_state(&parsed, &mut match_sync.tower_control);
+                        }
+                        Some("coop_state") if !network_client.is_host() => {
+                            apply_coop_state(&parsed, &mut match_sync.coop_waves);
+                        }
+                        Some("rainmaker_state") if !network_client.is_host() => {
+                            apply_rainmaker_state(&parsed, &mut match_sync.rainmaker, &mut painter);
                         }
                         _ => {
                             info!("❓ Unknown message: {}", message);
@@ -245,6 +942,287 @@ pub fn handle_udp_messages(network_client: Res<NetworkClient>, mut _game_state:
             }
         }
     }
+
+    if let Some(granted) = host_granted {
+        if network_client.host && !granted {
+            warn!("Another client is already hosting this game");
+        }
+        network_client.host = granted;
+    }
+}
+
+fn parse_player_state(player: &serde_json::Value) -> Option<PlayerState> {
+    Some(PlayerState {
+        id: uuid::Uuid::parse_str(player["id"].as_str()?).ok()?,
+        position: Vec2::new(
+            player["position"]["x"].as_f64()? as f32,
+            player["position"]["y"].as_f64()? as f32,
+        ),
+        health: player["health"].as_i64().unwrap_or(100) as i32,
+        team: Team::from_name(player["team"].as_str()?)?,
+        submerged: player["submerged"].as_bool().unwrap_or(false),
+        tick: 0,
+    })
+}
+
+fn parse_shot(message_type: &str, parsed: &serde_json::Value) -> Option<ShotFiredEvent> {
+    Some(ShotFiredEvent {
+        shooter: uuid::Uuid::parse_str(parsed["player_id"].as_str()?).ok()?,
+        team: Team::from_name(parsed["team"].as_str()?)?,
+        weapon: parsed["weapon"]
+            .as_str()
+            .unwrap_or(DEFAULT_WEAPON)
+            .to_string(),
+        kind: WeaponKind::from_message_type(message_type)?,
+        origin: Vec2::new(
+            parsed["position"]["x"].as_f64()? as f32,
+            parsed["position"]["y"].as_f64()? as f32,
+        ),
+        direction: Vec2::new(
+            parsed["direction"]["x"].as_f64()? as f32,
+            parsed["direction"]["y"].as_f64()? as f32,
+        ),
+        charge: parsed["charge"].as_f64().unwrap_or(0.0) as f32,
+        ink: parsed["ink"].as_f64().unwrap_or(0.0) as f32,
+    })
+}
+
+fn parse_sub_weapon_throw(parsed: &serde_json::Value) -> Option<SubWeaponThrownEvent> {
+    Some(SubWeaponThrownEvent {
+        thrower: uuid::Uuid::parse_str(parsed["player_id"].as_str()?).ok()?,
+        team: Team::from_name(parsed["team"].as_str()?)?,
+        sub_weapon: parsed["sub_weapon"]
+            .as_str()
+            .unwrap_or(DEFAULT_SUB_WEAPON)
+            .to_string(),
+        origin: Vec2::new(
+            parsed["position"]["x"].as_f64()? as f32,
+            parsed["position"]["y"].as_f64()? as f32,
+        ),
+        target: Vec2::new(
+            parsed["target"]["x"].as_f64()? as f32,
+            parsed["target"]["y"].as_f64()? as f32,
+        ),
+        ink: parsed["ink"].as_f64().unwrap_or(0.0) as f32,
+    })
+}
+
+// ガチエリアの持ち主とカウントはホストの判定に合わせる
+fn apply_splat_zones_state(parsed: &serde_json::Value, splat_zones: &mut SplatZones) {
+    if let Some(zones) = parsed["zones"].as_array() {
+        for zone_state in zones {
+            let Some(zone) = zone_state["index"]
+                .as_u64()
+                .and_then(|index| splat_zones.zones.get_mut(index as usize))
+            else {
+                continue;
+            };
+            zone.owner = zone_state["owner"].as_str().and_then(Team::from_name);
+        }
+    }
+
+    splat_zones.controller = parsed["controller"].as_str().and_then(Team::from_name);
+    for team in [Team::Blue, Team::Orange] {
+        if let Some(count) = parsed["counts"][team.name()].as_f64() {
+            splat_zones.counts.insert(team, count as f32);
+        }
+        if let Some(penalty) = parsed["penalties"][team.name()].as_f64() {
+            splat_zones.penalties.insert(team, penalty as f32);
+        }
+    }
+}
+
+// ヤグラの位置（通り道の始点からの距離）とチェックポイントの状態はホストに合わせる
+fn apply_tower_state(parsed: &serde_json::Value, tower_control: &mut TowerControl) {
+    if let Some(progress) = parsed["progress"].as_f64() {
+        tower_control.progress = (progress as f32).clamp(0.0, tower_control.length);
+    }
+    tower_control.controller = parsed["controller"].as_str().and_then(Team::from_name);
+    tower_control.contested = parsed["contested"].as_bool().unwrap_or(false);
+    for team in [Team::Blue, Team::Orange] {
+        if let Some(best) = parsed["best"][team.name()].as_f64() {
+            tower_control.best.insert(team, best as f32);
+        }
+    }
+    if let Some(cleared) = parsed["cleared"].as_array() {
+        for (flag, state) in tower_control.cleared.iter_mut().zip(cleared) {
+            *flag = state.as_bool().unwrap_or(*flag);
+        }
+    }
+    tower_control.checkpoint_wait = parsed["checkpoint_wait"]["team"]
+        .as_str()
+        .and_then(Team::from_name)
+        .zip(parsed["checkpoint_wait"]["remaining"].as_f64())
+        .map(|(team, remaining)| (team, remaining as f32));
+}
+
+// ガチホコの位置・シールド・記録はホストに合わせる。持ち主は拾った・やられた通知で決まる
+// ホストでシールドが割れていたら、割ったチームの色で周りを塗る
+fn apply_rainmaker_state(
+    parsed: &serde_json::Value,
+    rainmaker: &mut Rainmaker,
+    painter: &mut GroundPainter,
+) {
+    let was_shielded = rainmaker.shielded();
+    if let (Some(x), Some(y)) = (
+        parsed["position"]["x"].as_f64(),
+        parsed["position"]["y"].as_f64(),
+    ) {
+        rainmaker.position = Vec2::new(x as f32, y as f32);
+    }
+    if let Some(shield) = parsed["shield"].as_i64() {
+        rainmaker.shield = (shield as i32).clamp(0, RAINMAKER_SHIELD_HP);
+    }
+    rainmaker.broken_by = parsed["broken_by"].as_str().and_then(Team::from_name);
+    if let (true, false, Some(team)) = (was_shielded, rainmaker.shielded(), rainmaker.broken_by) {
+        painter.paint_circle(rainmaker.position, RAINMAKER_BURST_RADIUS, team.color());
+    }
+    for team in [Team::Blue, Team::Orange] {
+        if let Some(best) = parsed["best"][team.name()].as_f64() {
+            rainmaker.best.insert(team, (best as f32).clamp(0.0, 1.0));
+        }
+    }
+}
+
+// 協力モードのホストから届いた状態。敵と金イクラのエンティティは apply_coop_snapshot で合わせる
+fn apply_coop_state(parsed: &serde_json::Value, coop_waves: &mut CoopWaves) {
+    if let Some(wave) = parsed["wave"].as_u64() {
+        coop_waves.wave = wave as u32;
+    }
+    if let Some(delivered) = parsed["delivered"].as_u64() {
+        coop_waves.delivered = delivered as u32;
+    }
+    if let Some(remaining) = parsed["remaining"].as_f64() {
+        let elapsed = (COOP_WAVE_SECONDS - remaining as f32).max(0.0);
+        coop_waves
+            .wave_timer
+            .set_elapsed(std::time::Duration::from_secs_f32(elapsed));
+    }
+    coop_waves.failed = parsed["failed"].as_bool().unwrap_or(false);
+    coop_waves.cleared = parsed["cleared"].as_bool().unwrap_or(false);
+
+    if let Some(enemies) = parsed["enemies"].as_array() {
+        coop_waves.enemies = enemies
+            .iter()
+            .filter_map(|enemy| {
+                Some(EnemyState {
+                    id: enemy["id"].as_u64()? as u32,
+                    kind: EnemyKind::from_name(enemy["kind"].as_str()?)?,
+                    position: Vec2::new(enemy["x"].as_f64()? as f32, enemy["y"].as_f64()? as f32),
+                    health: enemy["health"].as_i64()? as i32,
+                })
+            })
+            .collect();
+    }
+    if let Some(eggs) = parsed["eggs"].as_array() {
+        coop_waves.eggs = eggs
+            .iter()
+            .filter_map(|egg| {
+                let position = Vec2::new(egg["x"].as_f64()? as f32, egg["y"].as_f64()? as f32);
+                Some((egg["id"].as_u64()? as u32, position))
+            })
+            .collect();
+    }
+}
+
+// サーバーから届いた塗り情報をタイル・壁とカバー率に反映。
+// このクライアントのプレイヤー（player_id）が塗ったものは、手元で塗ってスペシャルゲージにも数え済みなので読み飛ばす
+fn apply_painted_areas(
+    areas: &serde_json::Value,
+    local_players: &LocalPlayers,
+    painter: &mut GroundPainter,
+) {
+    let Some(areas) = areas.as_array() else {
+        return;
+    };
+
+    for area in areas {
+        let painter_id = area["player_id"]
+            .as_str()
+            .and_then(|id| uuid::Uuid::parse_str(id).ok());
+        if painter_id.is_some_and(|id| local_players.contains(id)) {
+            continue;
+        }
+
+        if let (Some(x), Some(y), Some(team)) = (
+            area["position"]["x"].as_i64(),
+            area["position"]["y"].as_i64(),
+            area["team"].as_str().and_then(Team::from_name),
+        ) {
+            let tile = (x as i32, y as i32);
+            // 壁の塗りは surface: "wall" で届く。カバー率やゲージには数えない
+            if area["surface"].as_str() == Some("wall") {
+                painter.paint_wall(tile, team.color());
+                continue;
+            }
+
+            painter.ink_tile(tile, team.color());
+        }
+    }
+}
+
+// 自分の被弾とやられをサーバーへ報告する
+pub fn send_combat_events_udp(
+    mut hit_events: EventReader<PlayerHitEvent>,
+    mut splat_events: EventReader<PlayerSplattedEvent>,
+    network_client: Res<NetworkClient>,
+    local_players: LocalPlayers,
+    player_query: Query<(&Player, &Health)>,
+) {
+    if network_client.sender.is_none() {
+        hit_events.clear();
+        splat_events.clear();
+        return;
+    }
+
+    for event in hit_events.read() {
+        if !local_players.contains(event.target) {
+            continue;
+        }
+        let Some(sender) = network_client.sender_for(event.target) else {
+            continue;
+        };
+        let health = player_query
+            .iter()
+            .find(|(player, _)| player.id == event.target)
+            .map_or(0, |(_, health)| health.0.max(0));
+
+        let message = json!({
+            "type": "player_hit",
+            "player_id": event.target.to_string(),
+            "attacker_id": event.attacker.to_string(),
+            "damage": event.damage,
+            "health": health
+        });
+
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send hit report: {}", e);
+        }
+    }
+
+    for event in splat_events.read() {
+        if !local_players.contains(event.victim) {
+            continue;
+        }
+        let Some(sender) = network_client.sender_for(event.victim) else {
+            continue;
+        };
+
+        let message = json!({
+            "type": "player_splatted",
+            "player_id": event.victim.to_string(),
+            "attacker_id": event.attacker.to_string(),
+            "attacker_team": event.attacker_team.name(),
+            "position": {
+                "x": event.position.x,
+                "y": event.position.y
+            }
+        });
+
+        if let Err(e) = sender.send(message.to_string()) {
+            warn!("Failed to send splat report: {}", e);
+        }
+    }
 }
 
 // ネットワークの接続状態を監視
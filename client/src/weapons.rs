@@ -0,0 +1,347 @@
+use crate::combat::*;
+use crate::components::*;
+use crate::events::*;
+use crate::paint::*;
+use crate::resources::*;
+use bevy::prelude::*;
+
+pub const BEAM_SECONDS: f32 = 0.15;
+// ボムの放物線の最高点（見た目のみ）
+pub const BOMB_ARC_HEIGHT: f32 = 48.0;
+// チャージャーは最低でもこの割合の射程とダメージを持つ
+pub const MIN_CHARGE: f32 = 0.3;
+
+// 射撃で塗る範囲。サーバーが同じ範囲を塗れるよう射撃メッセージに付けて送る
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PaintStroke {
+    pub from: Vec2,
+    pub to: Vec2,
+    // 線に沿って塗る半径。0 なら線上のタイルだけ
+    pub radius: f32,
+    // 線の終わりに塗り広げる半径
+    pub splash: f32,
+}
+
+impl PaintStroke {
+    // 壁で止まる前の、射程いっぱいまで届いたときの塗り跡
+    pub fn of_shot(shot: &ShotFiredEvent, stats: &WeaponDef) -> Self {
+        let charge = if stats.charge_time > 0.0 || shot.kind == WeaponKind::Charger {
+            shot.charge.clamp(MIN_CHARGE, 1.0)
+        } else {
+            1.0
+        };
+        let reach = shot.origin + shot.direction * stats.range * charge;
+
+        match shot.kind {
+            WeaponKind::Shooter => Self {
+                from: shot.origin,
+                to: reach,
+                radius: 0.0,
+                splash: stats.paint_radius,
+            },
+            WeaponKind::Blaster => Self {
+                from: shot.origin,
+                to: reach,
+                radius: 0.0,
+                splash: stats.explosion_radius * charge,
+            },
+            WeaponKind::Roller => {
+                let across = shot.direction.perp() * stats.roll_width / 2.0;
+                Self {
+                    from: shot.origin - across,
+                    to: shot.origin + across,
+                    radius: 0.0,
+                    splash: 0.0,
+                }
+            }
+            WeaponKind::Charger => Self {
+                from: shot.origin,
+                to: reach,
+                radius: stats.paint_radius,
+                splash: 0.0,
+            },
+        }
+    }
+}
+
+// 撃たれた武器の種類ごとに弾の生成・塗り・当たり判定を行う
+pub fn resolve_shots(
+    mut commands: Commands,
+    mut shot_events: EventReader<ShotFiredEvent>,
+    weapon_registry: Res<WeaponRegistry>,
+    arena: Res<Arena>,
+    mut painter: GroundPainter,
+    mut combatants: Combatants,
+) {
+    for shot in shot_events.read() {
+        let stats = weapon_registry.get(&shot.weapon);
+        let color = shot.team.color();
+
+        match shot.kind {
+            WeaponKind::Shooter | WeaponKind::Blaster => {
+                // 向きのない弾は止まったまま消えなくなるので出さない
+                if shot.direction == Vec2::ZERO {
+                    continue;
+                }
+                // 溜めて撃つ武器は溜め具合に応じて射程と爆発が小さくなる
+                let charge = if stats.charge_time > 0.0 {
+                    shot.charge.clamp(MIN_CHARGE, 1.0)
+                } else {
+                    1.0
+                };
+                let explosion_radius = if shot.kind == WeaponKind::Blaster {
+                    stats.explosion_radius * charge
+                } else {
+                    0.0
+                };
+
+                commands.spawn((
+                    Projectile {
+                        owner: shot.shooter,
+                        team: shot.team,
+                        color,
+                        origin: shot.origin,
+                        range: stats.range * charge,
+                        damage: stats.damage,
+                        paint_radius: stats.paint_radius,
+                        explosion_radius,
+                        next_drip: PROJECTILE_DRIP_INTERVAL,
+                    },
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(Vec2::splat(stats.projectile_size)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(shot.origin.x, shot.origin.y, 1.0),
+                        ..default()
+                    },
+                    Velocity(shot.direction * stats.projectile_speed),
+                    SimulatedPosition::new(shot.origin),
+                ));
+            }
+            WeaponKind::Roller => {
+                // 進行方向と垂直な帯を塗り、帯に触れた敵を轢く
+                let across = shot.direction.perp() * stats.roll_width / 2.0;
+                let half_width = stats.roll_width / 2.0;
+                let inked =
+                    painter.paint_line(shot.origin - across, shot.origin + across, 0.0, color);
+                painter.credit(shot.shooter, inked);
+
+                combatants.strike(shot.shooter, shot.team, stats.damage, &[], |target| {
+                    target.distance(shot.origin) <= half_width
+                });
+            }
+            WeaponKind::Charger => {
+                // 溜め具合に応じた射程の直線を一瞬で撃ち抜く
+                let charge = shot.charge.clamp(MIN_CHARGE, 1.0);
+                // 壁の手前までしか届かない
+                let range = arena
+                    .first_wall_on_line(shot.origin, shot.direction, stats.range * charge)
+                    .unwrap_or(stats.range * charge);
+                let hit = combatants.first_on_line(shot.team, shot.origin, shot.direction, range);
+                let length = hit.unwrap_or(range);
+
+                let inked = painter.paint_line(
+                    shot.origin,
+                    shot.origin + shot.direction * length,
+                    stats.paint_radius,
+                    color,
+                );
+                painter.credit(shot.shooter, inked);
+
+                if let Some(distance) = hit {
+                    let impact = shot.origin + shot.direction * distance;
+                    let damage = (stats.damage as f32 * charge) as i32;
+                    combatants.strike(shot.shooter, shot.team, damage, &[], |target| {
+                        target.distance(impact) <= PLAYER_HIT_RADIUS
+                    });
+                }
+
+                let center = shot.origin + shot.direction * length / 2.0;
+                commands.spawn((
+                    BeamEffect(Timer::from_seconds(BEAM_SECONDS, TimerMode::Once)),
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(Vec2::new(length, 4.0)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(center.x, center.y, 2.0).with_rotation(
+                            Quat::from_rotation_z(shot.direction.y.atan2(shot.direction.x)),
+                        ),
+                        ..default()
+                    },
+                ));
+            }
+        }
+    }
+}
+
+// 投げられたサブウェポンのボムを生成する
+pub fn resolve_sub_weapon_throws(
+    mut commands: Commands,
+    mut throw_events: EventReader<SubWeaponThrownEvent>,
+    sub_weapon_registry: Res<SubWeaponRegistry>,
+) {
+    for throw in throw_events.read() {
+        let stats = sub_weapon_registry.get(&throw.sub_weapon);
+
+        commands.spawn((
+            Bomb {
+                owner: throw.thrower,
+                team: throw.team,
+                start: throw.origin,
+                target: throw.target,
+                flight: Timer::from_seconds(stats.flight_time, TimerMode::Once),
+                fuse: Timer::from_seconds(stats.fuse_time, TimerMode::Once),
+                explosion_radius: stats.explosion_radius,
+                damage: stats.damage,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: throw.team.color(),
+                    custom_size: Some(Vec2::splat(14.0)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(throw.origin.x, throw.origin.y, 2.0),
+                ..default()
+            },
+            SimulatedPosition::new(throw.origin),
+        ));
+    }
+}
+
+// ボムを放物線に沿って飛ばし、着地後は導火線が尽きたら爆発させる
+pub fn update_bombs(
+    mut commands: Commands,
+    mut bomb_query: Query<(Entity, &mut Bomb, &mut Transform, &mut Sprite)>,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut bomb, mut transform, mut sprite) in bomb_query.iter_mut() {
+        if !bomb.flight.tick(time.delta()).finished() {
+            let t = bomb.flight.percent();
+            let height = BOMB_ARC_HEIGHT * 4.0 * t * (1.0 - t);
+            let ground = bomb.start.lerp(bomb.target, t);
+            transform.translation.x = ground.x;
+            transform.translation.y = ground.y + height;
+            transform.scale = Vec3::splat(1.0 + height / BOMB_ARC_HEIGHT * 0.5);
+            continue;
+        }
+
+        transform.translation.x = bomb.target.x;
+        transform.translation.y = bomb.target.y;
+        transform.scale = Vec3::ONE;
+
+        // 導火線の間は点滅する
+        let blink = (bomb.fuse.elapsed_secs() * 10.0) as i32 % 2 == 0;
+        sprite.color.set_a(if blink { 1.0 } else { 0.4 });
+
+        if bomb.fuse.tick(time.delta()).just_finished() {
+            explosion_events.send(ExplosionEvent {
+                owner: bomb.owner,
+                team: bomb.team,
+                position: bomb.target,
+                radius: bomb.explosion_radius,
+                damage: bomb.damage,
+                spared: Vec::new(),
+            });
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn fade_beams(
+    mut commands: Commands,
+    mut beam_query: Query<(Entity, &mut BeamEffect)>,
+    time: Res<Time>,
+) {
+    for (entity, mut beam) in beam_query.iter_mut() {
+        if beam.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// 武器を切り替える（人は Q キー）。ガチホコを持っている間は切り替えられない
+#[allow(clippy::type_complexity)]
+pub fn switch_weapon(
+    weapon_registry: Res<WeaponRegistry>,
+    mut player_query: Query<
+        (
+            &PlayerInput,
+            &mut Weapon,
+            &mut FireCooldown,
+            &mut ChargeState,
+            &mut RollState,
+        ),
+        (With<Player>, Without<RainmakerCarrier>),
+    >,
+) {
+    for (input, mut weapon, mut cooldown, mut charge, mut roll) in player_query.iter_mut() {
+        if !input.switch_weapon {
+            continue;
+        }
+        let Some(next) = weapon_registry.next_after(&weapon.id) else {
+            continue;
+        };
+
+        weapon.id = next.to_string();
+        weapon.stats = weapon_registry.get(next);
+        cooldown.restart(weapon.stats.fire_rate);
+        charge.charge = 0.0;
+        roll.last_position = None;
+        info!(
+            "🔫 Switched to {} ({:?})",
+            weapon.stats.name, weapon.stats.kind
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn shot(kind: WeaponKind, charge: f32) -> ShotFiredEvent {
+        ShotFiredEvent {
+            shooter: Uuid::nil(),
+            team: Team::Blue,
+            weapon: DEFAULT_WEAPON.to_string(),
+            kind,
+            origin: Vec2::new(64.0, 0.0),
+            direction: Vec2::X,
+            charge,
+            ink: 100.0,
+        }
+    }
+
+    #[test]
+    fn roller_stroke_crosses_the_direction() {
+        let stats = WeaponDef {
+            roll_width: 96.0,
+            ..default()
+        };
+        let stroke = PaintStroke::of_shot(&shot(WeaponKind::Roller, 0.0), &stats);
+        assert_eq!(stroke.from, Vec2::new(64.0, -48.0));
+        assert_eq!(stroke.to, Vec2::new(64.0, 48.0));
+        assert_eq!(stroke.splash, 0.0);
+    }
+
+    #[test]
+    fn charger_stroke_reaches_with_the_charge() {
+        let stats = WeaponDef {
+            range: 400.0,
+            paint_radius: 16.0,
+            charge_time: 1.0,
+            ..default()
+        };
+        let half = PaintStroke::of_shot(&shot(WeaponKind::Charger, 0.5), &stats);
+        assert_eq!(half.to, Vec2::new(264.0, 0.0));
+        assert_eq!(half.radius, 16.0);
+        // 溜めていなくても最低限の射程はある
+        let tap = PaintStroke::of_shot(&shot(WeaponKind::Charger, 0.0), &stats);
+        assert_eq!(tap.to, Vec2::new(64.0 + 400.0 * MIN_CHARGE, 0.0));
+    }
+}
@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// マップファイル（assets/maps/<id>.ron）の定義。
+// サーバーからも同じファイルを読めるよう bevy には依存させない
+
+// タイルの種類。マップファイルでは1タイル1文字で書く
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TileKind {
+    Paintable,
+    Wall,
+    Unpaintable,
+    // 水場・場外。入れず塗れない
+    Water,
+}
+
+impl TileKind {
+    pub fn from_char(c: char) -> Option<TileKind> {
+        match c {
+            '.' => Some(TileKind::Paintable),
+            '#' => Some(TileKind::Wall),
+            '_' => Some(TileKind::Unpaintable),
+            '~' => Some(TileKind::Water),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            TileKind::Paintable => '.',
+            TileKind::Wall => '#',
+            TileKind::Unpaintable => '_',
+            TileKind::Water => '~',
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ObjectiveKind {
+    SplatZone,
+    Tower,
+    Rainmaker,
+    Goal,
+}
+
+// ルール用の目印。position は左下のタイル、size はタイル数
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ObjectiveDef {
+    pub kind: ObjectiveKind,
+    pub position: (i32, i32),
+    #[serde(default = "ObjectiveDef::default_size")]
+    pub size: (i32, i32),
+    // ゴールなどチームに属する目印（"blue" / "orange"）
+    #[serde(default)]
+    pub team: Option<String>,
+}
+
+impl ObjectiveDef {
+    fn default_size() -> (i32, i32) {
+        (1, 1)
+    }
+
+    pub fn contains(&self, tile: (i32, i32)) -> bool {
+        tile.0 >= self.position.0
+            && tile.0 < self.position.0 + self.size.0
+            && tile.1 >= self.position.1
+            && tile.1 < self.position.1 + self.size.1
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapDef {
+    pub name: String,
+    // 左下のタイル座標
+    pub origin: (i32, i32),
+    // 上の行から順に並べたタイル。全行同じ長さにする
+    pub tiles: Vec<String>,
+    // チーム名 → リスポーン地点のタイル座標
+    pub spawns: HashMap<String, (i32, i32)>,
+    #[serde(default)]
+    pub objectives: Vec<ObjectiveDef>,
+    // ガチヤグラの通り道。blue 側の端から orange 側の端までの折れ線（タイル座標）
+    #[serde(default)]
+    pub tower_path: Vec<(i32, i32)>,
+}
+
+impl Default for MapDef {
+    // 40×30 の塗れる床だけのマップ
+    fn default() -> Self {
+        Self {
+            name: "Open Field".to_string(),
+            origin: (-20, -15),
+            tiles: vec![".".repeat(40); 30],
+            spawns: HashMap::from([
+                ("blue".to_string(), (-18, 0)),
+                ("orange".to_string(), (17, 0)),
+            ]),
+            objectives: Vec::new(),
+            tower_path: Vec::new(),
+        }
+    }
+}
+
+impl MapDef {
+    pub fn width(&self) -> i32 {
+        self.tiles
+            .first()
+            .map_or(0, |row| row.chars().count() as i32)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.tiles.len() as i32
+    }
+
+    pub fn contains(&self, tile: (i32, i32)) -> bool {
+        let (x, y) = (tile.0 - self.origin.0, tile.1 - self.origin.1);
+        x >= 0 && x < self.width() && y >= 0 && y < self.height()
+    }
+
+    // マップの外は水場として扱う
+    pub fn tile(&self, tile: (i32, i32)) -> TileKind {
+        if !self.contains(tile) {
+            return TileKind::Water;
+        }
+
+        let row = (self.height() - 1 - (tile.1 - self.origin.1)) as usize;
+        let column = (tile.0 - self.origin.0) as usize;
+        self.tiles[row]
+            .chars()
+            .nth(column)
+            .and_then(TileKind::from_char)
+            .unwrap_or(TileKind::Water)
+    }
+
+    // タイルを書き換える。マップの外や同じ種類なら false
+    pub fn set_tile(&mut self, tile: (i32, i32), kind: TileKind) -> bool {
+        if !self.contains(tile) || self.tile(tile) == kind {
+            return false;
+        }
+
+        let row = (self.height() - 1 - (tile.1 - self.origin.1)) as usize;
+        let column = (tile.0 - self.origin.0) as usize;
+        self.tiles[row] = self.tiles[row]
+            .chars()
+            .enumerate()
+            .map(|(i, c)| if i == column { kind.to_char() } else { c })
+            .collect();
+        true
+    }
+
+    pub fn iter_tiles(&self) -> impl Iterator<Item = ((i32, i32), TileKind)> + '_ {
+        let height = self.height();
+        self.tiles.iter().enumerate().flat_map(move |(row, line)| {
+            line.chars().enumerate().map(move |(column, c)| {
+                let tile = (
+                    self.origin.0 + column as i32,
+                    self.origin.1 + height - 1 - row as i32,
+                );
+                (tile, TileKind::from_char(c).unwrap_or(TileKind::Water))
+            })
+        })
+    }
+
+    pub fn count(&self, kind: TileKind) -> usize {
+        self.iter_tiles().filter(|(_, tile)| *tile == kind).count()
+    }
+
+    pub fn spawn(&self, team: &str) -> Option<(i32, i32)> {
+        self.spawns.get(team).copied()
+    }
+
+    // 行の長さ・文字・リスポーン地点・ヤグラの通り道が正しいか確かめる
+    pub fn validate(&self) -> Result<(), String> {
+        let width = self.width();
+        if width == 0 {
+            return Err("map has no tiles".to_string());
+        }
+
+        for (row, line) in self.tiles.iter().enumerate() {
+            if line.chars().count() as i32 != width {
+                return Err(format!("row {} is not {} tiles wide", row, width));
+            }
+            if let Some(c) = line.chars().find(|c| TileKind::from_char(*c).is_none()) {
+                return Err(format!("unknown tile '{}' in row {}", c, row));
+            }
+        }
+
+        for team in ["blue", "orange"] {
+            match self.spawn(team) {
+                Some(spawn)
+                    if matches!(
+                        self.tile(spawn),
+                        TileKind::Paintable | TileKind::Unpaintable
+                    ) => {}
+                Some(spawn) => return Err(format!("{} spawn {:?} is not on floor", team, spawn)),
+                None => return Err(format!("missing {} spawn", team)),
+            }
+        }
+
+        // 通り道はガチヤグラ以外では空でよい。折れ線なので 2 点以上要り、点と点の間も床の上を通る
+        if self.tower_path.len() == 1 {
+            return Err("tower path needs at least 2 points".to_string());
+        }
+        for segment in self.tower_path.windows(2) {
+            if let Some(tile) = path_tiles(segment[0], segment[1]).find(|tile| {
+                !matches!(
+                    self.tile(*tile),
+                    TileKind::Paintable | TileKind::Unpaintable
+                )
+            }) {
+                return Err(format!("tower path {:?} is not on floor", tile));
+            }
+        }
+        Ok(())
+    }
+}
+
+// from から to まで半タイルずつたどったときに通るタイル
+fn path_tiles(from: (i32, i32), to: (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+    let (dx, dy) = ((to.0 - from.0) as f32, (to.1 - from.1) as f32);
+    let steps = ((dx.hypot(dy) * 2.0).ceil() as i32).max(1);
+    (0..=steps).map(move |step| {
+        let t = step as f32 / steps as f32;
+        (
+            (from.0 as f32 + dx * t).round() as i32,
+            (from.1 as f32 + dy * t).round() as i32,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_map_is_valid() {
+        assert_eq!(MapDef::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn bundled_maps_are_valid() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../client/assets/maps");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let map: MapDef = ron::from_str(&contents).unwrap();
+            assert_eq!(map.validate(), Ok(()), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn rejects_rows_of_different_widths() {
+        let mut map = MapDef::default();
+        map.tiles[3].push('.');
+        assert_eq!(
+            map.validate(),
+            Err("row 3 is not 40 tiles wide".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_tiles() {
+        let mut map = MapDef::default();
+        map.tiles[0] = format!("x{}", ".".repeat(39));
+        assert_eq!(map.validate(), Err("unknown tile 'x' in row 0".to_string()));
+    }
+
+    #[test]
+    fn rejects_spawns_off_the_floor() {
+        let mut map = MapDef::default();
+        let spawn = map.spawn("blue").unwrap();
+        map.set_tile(spawn, TileKind::Wall);
+        assert!(map.validate().is_err());
+
+        let mut map = MapDef::default();
+        map.spawns.remove("orange");
+        assert_eq!(map.validate(), Err("missing orange spawn".to_string()));
+    }
+
+    #[test]
+    fn rejects_tower_paths_that_are_too_short_or_cross_walls() {
+        let mut map = MapDef {
+            tower_path: vec![(-5, 0)],
+            ..MapDef::default()
+        };
+        assert_eq!(
+            map.validate(),
+            Err("tower path needs at least 2 points".to_string())
+        );
+
+        map.tower_path = vec![(-5, 0), (5, 0)];
+        assert_eq!(map.validate(), Ok(()));
+        map.set_tile((0, 0), TileKind::Wall);
+        assert_eq!(
+            map.validate(),
+            Err("tower path (0, 0) is not on floor".to_string())
+        );
+    }
+
+    #[test]
+    fn set_tile_is_read_back_at_the_same_tile() {
+        let mut map = MapDef::default();
+        assert!(map.set_tile((3, -4), TileKind::Water));
+        assert!(!map.set_tile((3, -4), TileKind::Water));
+        assert_eq!(map.tile((3, -4)), TileKind::Water);
+        assert_eq!(map.count(TileKind::Water), 1);
+        // マップの外は水場で、書き換えられない
+        assert_eq!(map.tile((100, 0)), TileKind::Water);
+        assert!(!map.set_tile((100, 0), TileKind::Wall));
+    }
+}
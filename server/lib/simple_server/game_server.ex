@@ -1,11 +1,18 @@
 defmodule SimpleServer.GameServer do
   use GenServer
 
+  alias SimpleServer.MapData
+
+  # クライアントの TILE_SIZE と同じ。塗りはワールド座標（ピクセル）をタイル座標に直して持つ
+  @tile_size 32
+
   defmodule State do
     defstruct [
       :game_id,
       :players,
       :painted_tiles,
+      :painted_walls,
+      :map,
       :clients
     ]
   end
@@ -15,7 +22,8 @@ defmodule SimpleServer.GameServer do
       :id,
       :position,
       :team,
-      :health
+      :health,
+      submerged: false
     ]
   end
 
@@ -27,12 +35,19 @@ defmodule SimpleServer.GameServer do
     GenServer.call(via_tuple(game_id), {:join_game, player_id, team, client_key})
   end
 
-  def update_player_position(game_id, player_id, position) do
-    GenServer.cast(via_tuple(game_id), {:update_position, player_id, position})
+  # tick は送り主のティック。そのまま player_update に付け、受け取った側が古い位置を捨てるのに使う
+  def update_player_position(game_id, player_id, position, submerged \\ false, tick \\ nil) do
+    GenServer.cast(via_tuple(game_id), {:update_position, player_id, position, submerged, tick})
+  end
+
+  # 射撃メッセージの paint の範囲を塗る。撃つ前のインク（ink）が消費量（ink_cost）に足りなければ断る
+  def shoot(game_id, player_id, shot) do
+    GenServer.call(via_tuple(game_id), {:shoot, player_id, shot})
   end
 
-  def shoot(game_id, player_id, direction) do
-    GenServer.cast(via_tuple(game_id), {:shoot, player_id, direction})
+  # サブウェポンのボムが爆発する fuse_time 秒後に、target の周りを explosion_radius の範囲で塗る
+  def throw_sub_weapon(game_id, player_id, throw) do
+    GenServer.cast(via_tuple(game_id), {:throw_sub_weapon, player_id, throw})
   end
 
   def remove_client(game_id, client_key) do
@@ -47,6 +62,8 @@ defmodule SimpleServer.GameServer do
       game_id: game_id,
       players: %{},
       painted_tiles: %{},
+      painted_walls: %{},
+      map: MapData.load(),
       clients: []
     }
     
@@ -72,35 +89,52 @@ defmodule SimpleServer.GameServer do
   end
 
   @impl true
-  def handle_cast({:update_position, player_id, position}, state) do
+  def handle_call({:shoot, player_id, shot}, _from, state) do
+    case Map.get(state.players, player_id) do
+      nil -> {:reply, {:error, :unknown_player}, state}
+      player ->
+        if enough_ink?(shot) do
+          tiles = stroke_tiles(state.map, Map.get(shot, "paint"))
+          new_state = paint_tiles(state, player_id, player.team, tiles)
+          
+          {:reply, :ok, new_state}
+        else
+          {:reply, {:error, :not_enough_ink}, state}
+        end
+    end
+  end
+
+  @impl true
+  def handle_cast({:update_position, player_id, position, submerged, tick}, state) do
     case Map.get(state.players, player_id) do
       nil -> {:noreply, state}
       player ->
-        updated_player = %{player | position: position}
+        updated_player = %{player | position: position, submerged: submerged}
         new_players = Map.put(state.players, player_id, updated_player)
         new_state = %{state | players: new_players}
         
-        broadcast_player_update(new_state, player_id, position)
+        broadcast_player_update(new_state, player_id, position, submerged, tick)
         
         {:noreply, new_state}
     end
   end
 
   @impl true
-  def handle_cast({:shoot, player_id, direction}, state) do
+  def handle_cast({:throw_sub_weapon, player_id, %{"target" => %{"x" => x, "y" => y}} = throw}, state) do
     case Map.get(state.players, player_id) do
       nil -> {:noreply, state}
       player ->
-        painted_areas = calculate_paint_trajectory(player.position, direction, player.team)
-        new_painted_tiles = Map.merge(state.painted_tiles, painted_areas)
-        new_state = %{state | painted_tiles: new_painted_tiles}
-        
-        broadcast_paint_update(new_state, painted_areas)
-        
-        {:noreply, new_state}
+        radius = Map.get(throw, "explosion_radius", 0)
+        fuse_ms = max(round(Map.get(throw, "fuse_time", 0) * 1000), 0)
+        Process.send_after(self(), {:explode, player_id, player.team, {x, y}, radius}, fuse_ms)
+        {:noreply, state}
     end
   end
 
+  def handle_cast({:throw_sub_weapon, _player_id, _throw}, state) do
+    {:noreply, state}
+  end
+
   @impl true
   def handle_cast({:remove_client, client_key}, state) do
     new_clients = List.delete(state.clients, client_key)
@@ -108,50 +142,120 @@ defmodule SimpleServer.GameServer do
     {:noreply, new_state}
   end
 
+  @impl true
+  def handle_info({:explode, player_id, team, target, radius}, state) do
+    {:noreply, paint_tiles(state, player_id, team, circle_tiles(target, radius))}
+  end
+
+  # 撃つ・投げる前のインク（ink）が消費量（ink_cost）に足りているか
+  def enough_ink?(%{"ink" => ink, "ink_cost" => ink_cost}) when is_number(ink) and is_number(ink_cost) do
+    ink >= ink_cost
+  end
+
+  def enough_ink?(_message), do: false
+
   # Private functions
 
   defp via_tuple(game_id) do
     {:via, Registry, {SimpleServer.GameRegistry, game_id}}
   end
 
-  defp calculate_paint_trajectory({x, y}, {dx, dy}, team) do
-    steps = 10
-    paint_radius = 1
-    
-    for step <- 1..steps,
-        tile_x <- trunc(x + dx * step) - paint_radius..trunc(x + dx * step) + paint_radius,
-        tile_y <- trunc(y + dy * step) - paint_radius..trunc(y + dy * step) + paint_radius,
-        into: %{} do
-      {{tile_x, tile_y}, team}
+  # 塗ったタイルを床と壁に分けて覚え、他のクライアントへ送る。マップ上で塗れないタイルは捨てる
+  defp paint_tiles(state, player_id, team, tiles) do
+    {walls, floors} =
+      tiles
+      |> Enum.map(fn tile -> {tile, MapData.tile_kind(state.map, tile)} end)
+      |> Enum.filter(fn {_tile, kind} -> kind in [:paintable, :wall] end)
+      |> Enum.split_with(fn {_tile, kind} -> kind == :wall end)
+
+    painted_floors = Map.new(floors, fn {tile, _kind} -> {tile, team} end)
+    painted_walls = Map.new(walls, fn {tile, _kind} -> {tile, team} end)
+    new_state = %{state |
+      painted_tiles: Map.merge(state.painted_tiles, painted_floors),
+      painted_walls: Map.merge(state.painted_walls, painted_walls)
+    }
+
+    broadcast_paint_update(new_state, player_id, painted_floors, painted_walls)
+    new_state
+  end
+
+  # クライアントの paint_line と同じく from から to まで半タイルごとに radius 以内のタイルを塗り、
+  # 最後に届いた所の周りを splash の半径で塗る。弾と同じく壁で止まり、当たった壁のタイルも塗る。
+  # 返すのはタイル座標
+  defp stroke_tiles(map, %{"from" => %{"x" => fx, "y" => fy}, "to" => %{"x" => tx, "y" => ty}} = paint) do
+    radius = Map.get(paint, "radius", 0)
+    splash = Map.get(paint, "splash", 0)
+    length = :math.sqrt((tx - fx) * (tx - fx) + (ty - fy) * (ty - fy))
+    steps = max(ceil(length / (@tile_size / 2)), 1)
+
+    points =
+      for step <- 0..steps do
+        t = step / steps
+        {fx + (tx - fx) * t, fy + (ty - fy) * t}
+      end
+    {reached, blocked} =
+      Enum.split_while(points, fn point -> MapData.tile_kind(map, world_to_tile(point)) != :wall end)
+
+    line = Enum.flat_map(reached, &circle_tiles(&1, radius))
+    hit_wall = blocked |> Enum.take(1) |> Enum.map(&world_to_tile/1)
+    landing = List.last(reached, {fx, fy})
+
+    Enum.uniq(line ++ hit_wall ++ circle_tiles(landing, splash))
+  end
+
+  defp stroke_tiles(_map, _paint), do: []
+
+  defp circle_tiles(center, radius) when radius <= 0, do: [world_to_tile(center)]
+
+  defp circle_tiles({x, y} = center, radius) do
+    {cx, cy} = world_to_tile(center)
+    reach = ceil(radius / @tile_size)
+
+    for tile_x <- (cx - reach)..(cx + reach),
+        tile_y <- (cy - reach)..(cy + reach),
+        :math.pow(tile_x * @tile_size - x, 2) + :math.pow(tile_y * @tile_size - y, 2) <= radius * radius do
+      {tile_x, tile_y}
     end
   end
 
+  defp world_to_tile({x, y}), do: {round(x / @tile_size), round(y / @tile_size)}
+
   defp broadcast_game_state(state) do
     message = %{
       type: "game_state",
       players: format_players(state.players),
-      painted_tiles: format_painted_tiles(state.painted_tiles)
+      painted_tiles: format_painted_tiles(state.painted_tiles) ++ format_painted_walls(state.painted_walls)
     }
     
     SimpleServer.UdpServer.broadcast_to_game_clients(state.game_id, message)
   end
 
-  defp broadcast_player_update(state, player_id, position) do
+  defp broadcast_player_update(state, player_id, position, submerged, tick) do
     message = %{
       type: "player_update",
       player_id: player_id,
-      position: %{x: elem(position, 0), y: elem(position, 1)}
+      position: %{x: elem(position, 0), y: elem(position, 1)},
+      submerged: submerged,
+      tick: tick
     }
     
     SimpleServer.UdpServer.broadcast_to_game_clients(state.game_id, message)
   end
 
-  defp broadcast_paint_update(state, painted_areas) do
-    formatted_areas = 
-      painted_areas
+  # player_id は塗ったプレイヤー。そのプレイヤーのクライアントは手元で塗り済みなので読み飛ばす。
+  # 壁のタイルには surface: "wall" を付ける
+  defp broadcast_paint_update(state, player_id, painted_floors, painted_walls) do
+    floors =
+      painted_floors
       |> Enum.map(fn {{x, y}, team} -> 
-        %{position: %{x: x, y: y}, team: team}
+        %{position: %{x: x, y: y}, team: team, player_id: player_id}
       end)
+    walls =
+      painted_walls
+      |> Enum.map(fn {{x, y}, team} ->
+        %{position: %{x: x, y: y}, team: team, player_id: player_id, surface: "wall"}
+      end)
+    formatted_areas = floors ++ walls
     
     message = %{
       type: "paint_update",
@@ -168,7 +272,8 @@ defmodule SimpleServer.GameServer do
         id: player.id,
         position: %{x: elem(player.position, 0), y: elem(player.position, 1)},
         team: player.team,
-        health: player.health
+        health: player.health,
+        submerged: player.submerged
       }
     end)
   end
@@ -179,4 +284,11 @@ defmodule SimpleServer.GameServer do
       %{position: %{x: x, y: y}, team: team}
     end)
   end
+
+  defp format_painted_walls(painted_walls) do
+    painted_walls
+    |> Enum.map(fn {{x, y}, team} ->
+      %{position: %{x: x, y: y}, team: team, surface: "wall"}
+    end)
+  end
 end
\ No newline at end of file
@@ -0,0 +1,63 @@
+defmodule SimpleServer.MapData do
+  require Logger
+
+  # クライアントと同じマップファイル（client/assets/maps/<id>.ron）から origin と tiles だけを読む。
+  # 場所は config の :simple_server, :map_path で変えられる
+  @default_map_path "../client/assets/maps/ink_plaza.ron"
+
+  defstruct [:origin, :rows, :width, :height]
+
+  # 読めなければ nil。そのときは全部を塗れる床として扱う
+  def load do
+    path = Application.get_env(:simple_server, :map_path, @default_map_path)
+
+    with {:ok, source} <- File.read(path),
+         {:ok, map} <- parse(source) do
+      Logger.info("Loaded map #{path} (#{map.width}x#{map.height})")
+      map
+    else
+      {:error, reason} ->
+        Logger.warn("Failed to load map #{path}: #{inspect(reason)}, painting every tile as floor")
+        nil
+    end
+  end
+
+  def parse(source) do
+    with [_, x, y] <- Regex.run(~r/origin:\s*\((-?\d+),\s*(-?\d+)\)/, source),
+         [_, tiles] <- Regex.run(~r/tiles:\s*\[(.*?)\]/s, source) do
+      rows =
+        ~r/"([^"]*)"/
+        |> Regex.scan(tiles, capture: :all_but_first)
+        |> Enum.map(fn [row] -> row end)
+
+      {:ok,
+       %__MODULE__{
+         origin: {String.to_integer(x), String.to_integer(y)},
+         rows: List.to_tuple(rows),
+         width: rows |> List.first("") |> byte_size(),
+         height: length(rows)
+       }}
+    else
+      _ -> {:error, :invalid_map}
+    end
+  end
+
+  # タイル座標の種類。MapDef::tile と同じく上の行から並んでいて、マップの外は水場
+  def tile_kind(nil, _tile), do: :paintable
+
+  def tile_kind(%__MODULE__{origin: {ox, oy}} = map, {x, y}) do
+    column = x - ox
+    row = map.height - 1 - (y - oy)
+
+    if column < 0 or column >= map.width or row < 0 or row >= map.height do
+      :water
+    else
+      case :binary.at(elem(map.rows, row), column) do
+        ?. -> :paintable
+        ?# -> :wall
+        ?_ -> :unpaintable
+        _ -> :water
+      end
+    end
+  end
+end
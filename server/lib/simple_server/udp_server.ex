@@ -4,6 +4,16 @@ defmodule SimpleServer.UdpServer do
 
   @port 8083
 
+  # クライアントが送ったまま、同じゲームの他のクライアントへ中継するメッセージ。
+  # 被弾とやられは撃たれた本人のクライアントが判定して報告する
+  @relayed_types ~w(player_ready rainmaker_pickup egg_collected egg_deposited player_hit player_splatted special_activated)
+  # ホストのクライアントだけが送れる試合・ルールの状態。ホスト以外から届いたものは捨てる
+  @host_state_types ~w(match_state splat_zones_state tower_state rainmaker_state coop_state)
+  # 武器の種類ごとの射撃メッセージ。どれもサーバーで塗ってから中継する
+  @shot_types ~w(player_shoot player_roll charger_shot blaster_shot)
+  # 協力モードで1つのチームに入れる人数
+  @max_coop_team_size 4
+
   def start_link(_) do
     GenServer.start_link(__MODULE__, [], name: __MODULE__)
   end
@@ -15,7 +25,7 @@ defmodule SimpleServer.UdpServer do
     # クリーンアップタイマーを開始
     Process.send_after(self(), :cleanup_clients, 30_000)
     
-    {:ok, %{socket: socket, clients: %{}}}
+    {:ok, %{socket: socket, clients: %{}, hosts: %{}}}
   end
 
   def handle_info({:udp, socket, ip, port, data}, state) do
@@ -28,39 +38,22 @@ defmodule SimpleServer.UdpServer do
     end
   end
 
-  defp handle_message(%{"type" => "join_game", "game_id" => game_id, "player_id" => player_id, "team" => team}, ip, port, state) do
+  defp handle_message(%{"type" => "join_game", "game_id" => game_id, "player_id" => player_id, "team" => team} = message, ip, port, state) do
     client_key = {ip, port}
-    
-    # ゲームサーバーが存在しない場合は作成
-    case SimpleServer.GameSupervisor.start_game(game_id) do
-      {:ok, _pid} -> :ok
-      {:error, {:already_started, _pid}} -> :ok
-      _ -> :error
+
+    if Map.get(message, "mode") == "coop" and team_size(state, game_id, team, client_key) >= @max_coop_team_size do
+      # 協力モードのチームが埋まっていれば参加させない
+      response = Jason.encode!(%{type: "join_rejected", player_id: player_id, reason: "team_full"})
+      :gen_udp.send(state.socket, ip, port, response)
+
+      Logger.info("Rejected #{player_id} from game #{game_id}: #{team} team is full")
+      {:noreply, state}
+    else
+      join_game(message, client_key, state)
     end
-    
-    # クライアント情報を保存
-    client_info = %{
-      player_id: player_id,
-      game_id: game_id,
-      team: team,
-      last_seen: System.monotonic_time(:millisecond)
-    }
-    
-    new_clients = Map.put(state.clients, client_key, client_info)
-    new_state = %{state | clients: new_clients}
-    
-    # ゲームサーバーに参加
-    SimpleServer.GameServer.join_game_udp(game_id, player_id, String.to_atom(team), client_key)
-    
-    # 接続確認メッセージを送信
-    response = Jason.encode!(%{type: "connected", player_id: player_id})
-    :gen_udp.send(state.socket, ip, port, response)
-    
-    Logger.info("Player #{player_id} joined game #{game_id} from #{inspect(ip)}:#{port}")
-    {:noreply, new_state}
   end
 
-  defp handle_message(%{"type" => "player_move", "position" => %{"x" => x, "y" => y}}, ip, port, state) do
+  defp handle_message(%{"type" => "player_move", "position" => %{"x" => x, "y" => y}} = message, ip, port, state) do
     client_key = {ip, port}
     
     case Map.get(state.clients, client_key) do
@@ -74,14 +67,16 @@ defmodule SimpleServer.UdpServer do
         new_clients = Map.put(state.clients, client_key, updated_client)
         new_state = %{state | clients: new_clients}
         
-        # ゲームサーバーに位置更新を送信
-        SimpleServer.GameServer.update_player_position(client_info.game_id, client_info.player_id, {x, y})
+        # ゲームサーバーに位置更新を送信（潜っているかどうかも他のクライアントへ伝える）
+        submerged = Map.get(message, "submerged", false) == true
+        tick = Map.get(message, "tick")
+        SimpleServer.GameServer.update_player_position(client_info.game_id, client_info.player_id, {x, y}, submerged, tick)
         
         {:noreply, new_state}
     end
   end
 
-  defp handle_message(%{"type" => "player_shoot", "direction" => %{"x" => x, "y" => y}}, ip, port, state) do
+  defp handle_message(%{"type" => type, "direction" => %{"x" => _x, "y" => _y}} = message, ip, port, state) when type in @shot_types do
     client_key = {ip, port}
     
     case Map.get(state.clients, client_key) do
@@ -95,10 +90,58 @@ defmodule SimpleServer.UdpServer do
         new_clients = Map.put(state.clients, client_key, updated_client)
         new_state = %{state | clients: new_clients}
         
-        # ゲームサーバーに射撃を送信
-        SimpleServer.GameServer.shoot(client_info.game_id, client_info.player_id, {x, y})
-        
-        {:noreply, new_state}
+        # ゲームサーバーに射撃を送信し、クライアントが付けた塗り跡（paint）をサーバーでも塗る。
+        # 認められた射撃は他のクライアントへ中継し、各クライアントで弾と当たり判定を再現させる
+        case SimpleServer.GameServer.shoot(client_info.game_id, client_info.player_id, message) do
+          :ok ->
+            relay_to_game(message, client_key, new_state)
+
+          {:error, reason} ->
+            Logger.warn("Rejected shot from #{client_info.player_id}: #{reason}")
+            {:noreply, new_state}
+        end
+    end
+  end
+
+  defp handle_message(%{"type" => "sub_weapon_throw"} = message, ip, port, state) do
+    client_key = {ip, port}
+
+    case Map.get(state.clients, client_key) do
+      nil ->
+        Logger.warn("Unknown client #{inspect(ip)}:#{port} tried to throw a sub weapon")
+        {:noreply, state}
+
+      client_info ->
+        # インクが足りていれば爆発の範囲をサーバーでも塗り、投擲を他のクライアントへ中継する
+        if SimpleServer.GameServer.enough_ink?(message) do
+          SimpleServer.GameServer.throw_sub_weapon(client_info.game_id, client_info.player_id, message)
+          relay_to_game(message, client_key, state)
+        else
+          Logger.warn("Rejected sub weapon throw from #{client_info.player_id}: not_enough_ink")
+          {:noreply, state}
+        end
+    end
+  end
+
+  defp handle_message(%{"type" => type} = message, ip, port, state) when type in @relayed_types do
+    relay_to_game(message, {ip, port}, state)
+  end
+
+  defp handle_message(%{"type" => type} = message, ip, port, state) when type in @host_state_types do
+    client_key = {ip, port}
+
+    case Map.get(state.clients, client_key) do
+      %{game_id: game_id} ->
+        if Map.get(state.hosts, game_id) == client_key do
+          relay_to_game(message, client_key, state)
+        else
+          Logger.warn("Non-host client #{inspect(ip)}:#{port} sent #{type}")
+          {:noreply, state}
+        end
+
+      nil ->
+        Logger.warn("Unknown client #{inspect(ip)}:#{port} sent #{type}")
+        {:noreply, state}
     end
   end
 
@@ -183,6 +226,72 @@ defmodule SimpleServer.UdpServer do
     {:noreply, state}
   end
 
+  # 送ってきたクライアント以外の、同じゲームのクライアントへそのまま届ける
+  defp relay_to_game(message, client_key, state) do
+    case Map.get(state.clients, client_key) do
+      nil ->
+        Logger.warn("Unknown client #{inspect(client_key)} sent #{message["type"]}")
+        {:noreply, state}
+
+      client_info ->
+        json_message = Jason.encode!(message)
+
+        state.clients
+        |> Enum.filter(fn {key, client} -> key != client_key and client.game_id == client_info.game_id end)
+        |> Enum.each(fn {{ip, port}, _client} ->
+          :gen_udp.send(state.socket, ip, port, json_message)
+        end)
+
+        updated_client = %{client_info | last_seen: System.monotonic_time(:millisecond)}
+        {:noreply, %{state | clients: Map.put(state.clients, client_key, updated_client)}}
+    end
+  end
+
+  defp join_game(%{"game_id" => game_id, "player_id" => player_id, "team" => team} = message, {ip, port} = client_key, state) do
+    # 最初に host: true で参加したクライアントがそのゲームのホストになる
+    {is_host, new_hosts} =
+      cond do
+        Map.get(message, "host", false) != true -> {false, state.hosts}
+        Map.has_key?(state.hosts, game_id) -> {Map.get(state.hosts, game_id) == client_key, state.hosts}
+        true -> {true, Map.put(state.hosts, game_id, client_key)}
+      end
+    
+    # ゲームサーバーが存在しない場合は作成
+    case SimpleServer.GameSupervisor.start_game(game_id) do
+      {:ok, _pid} -> :ok
+      {:error, {:already_started, _pid}} -> :ok
+      _ -> :error
+    end
+    
+    # クライアント情報を保存
+    client_info = %{
+      player_id: player_id,
+      game_id: game_id,
+      team: team,
+      last_seen: System.monotonic_time(:millisecond)
+    }
+    
+    new_clients = Map.put(state.clients, client_key, client_info)
+    new_state = %{state | clients: new_clients, hosts: new_hosts}
+    
+    # ゲームサーバーに参加
+    SimpleServer.GameServer.join_game_udp(game_id, player_id, String.to_atom(team), client_key)
+    
+    # 接続確認メッセージを送信
+    response = Jason.encode!(%{type: "connected", player_id: player_id, host: is_host})
+    :gen_udp.send(state.socket, ip, port, response)
+    
+    Logger.info("Player #{player_id} joined game #{game_id} from #{inspect(ip)}:#{port}")
+    {:noreply, new_state}
+  end
+
+  # そのゲームのチームに参加しているクライアントの数。参加し直すクライアント自身は数えない
+  defp team_size(state, game_id, team, client_key) do
+    Enum.count(state.clients, fn {key, client} ->
+      key != client_key and client.game_id == game_id and client.team == team
+    end)
+  end
+
   # パブリック関数：ゲームサーバーから呼び出される
   def broadcast_to_client(client_key, message) do
     GenServer.cast(__MODULE__, {:broadcast, client_key, message})
@@ -223,6 +332,12 @@ defmodule SimpleServer.UdpServer do
       end)
       |> Enum.into(%{})
     
+    # いなくなったホストの席を空け、次に host: true で参加したクライアントに譲る
+    active_hosts =
+      state.hosts
+      |> Enum.filter(fn {_game_id, client_key} -> Map.has_key?(active_clients, client_key) end)
+      |> Enum.into(%{})
+
     removed_count = map_size(state.clients) - map_size(active_clients)
     if removed_count > 0 do
       Logger.info("Cleaned up #{removed_count} inactive clients")
@@ -231,7 +346,7 @@ defmodule SimpleServer.UdpServer do
     # 次のクリーンアップをスケジュール
     Process.send_after(self(), :cleanup_clients, 30_000)
     
-    {:noreply, %{state | clients: active_clients}}
+    {:noreply, %{state | clients: active_clients, hosts: active_hosts}}
   end
 
   def handle_info(msg, state) do